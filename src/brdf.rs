@@ -0,0 +1,164 @@
+use std::f64::consts::PI;
+
+use color::Color;
+use math::Vector;
+
+#[derive(Copy, Clone, Debug)]
+pub enum DiffuseModel {
+    Lambert,
+    // `sigma` is the standard deviation of the groove slopes, in radians
+    OrenNayar { sigma: f64 },
+}
+
+impl DiffuseModel {
+    pub fn reflectance(&self, n: Vector, v: Vector, l: Vector) -> f64 {
+        let n_dot_l = n.dot(&l).max(0.);
+        match *self {
+            DiffuseModel::Lambert => n_dot_l,
+            DiffuseModel::OrenNayar { sigma } => {
+                let n_dot_v = n.dot(&v);
+                if n_dot_l <= 0. || n_dot_v <= 0. {
+                    return 0.;
+                }
+                let s2 = sigma.powi(2);
+                let a = 1. - 0.5 * s2 / (s2 + 0.33);
+                let b = 0.45 * s2 / (s2 + 0.09);
+                // Cosine of the azimuth between the light and the viewer
+                let l_t = l - n * n_dot_l;
+                let v_t = v - n * n_dot_v;
+                let cos_phi = match l_t.magnitude() * v_t.magnitude() {
+                    m if m > 0. => (l_t.dot(&v_t) / m).max(0.),
+                    _ => 0.,
+                };
+                let (theta_l, theta_v) = (n_dot_l.min(1.).acos(), n_dot_v.min(1.).acos());
+                let alpha = theta_l.max(theta_v);
+                let beta = theta_l.min(theta_v);
+                n_dot_l * (a + b * cos_phi * alpha.sin() * beta.tan())
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum SpecularModel {
+    Phong,
+    BlinnPhong,
+    // Roughness `ax` around the y axis and `ay` across it
+    Ward { ax: f64, ay: f64 },
+}
+
+impl SpecularModel {
+    pub fn reflectance(&self, n: Vector, v: Vector, l: Vector, ns: f64) -> f64 {
+        match *self {
+            SpecularModel::Phong => {
+                let r = l.reflect(&n).normalized();
+                r.dot(&v).max(0.).powf(ns)
+            }
+            SpecularModel::BlinnPhong => {
+                if n.dot(&l) <= 0. {
+                    return 0.;
+                }
+                let h = (v + l).normalized();
+                n.dot(&h).max(0.).powf(ns)
+            }
+            SpecularModel::Ward { ax, ay } => {
+                let n_dot_l = n.dot(&l);
+                let n_dot_v = n.dot(&v);
+                if n_dot_l <= 0. || n_dot_v <= 0. {
+                    return 0.;
+                }
+                let h = (v + l).normalized();
+                let (t, b) = tangent_frame(n);
+                let exponent =
+                    -((h.dot(&t) / ax).powi(2) + (h.dot(&b) / ay).powi(2)) / n.dot(&h).powi(2);
+                // Scaled by π like the Lambertian diffuse term
+                n_dot_l * exponent.exp() / (4. * ax * ay * (n_dot_l * n_dot_v).sqrt())
+            }
+        }
+    }
+}
+
+fn tangent_frame(n: Vector) -> (Vector, Vector) {
+    let up = Vector {
+        x: 0.,
+        y: 1.,
+        z: 0.,
+    };
+    let t = up.cross(&n);
+    if t.magnitude() < 1e-6 {
+        return n.perpendiculars();
+    }
+    let t = t.normalized();
+    (t, n.cross(&t))
+}
+
+// GGX microfacet model with Smith shadowing and Schlick's Fresnel
+#[derive(Copy, Clone, Debug)]
+pub struct MetallicRoughness {
+    pub base_color: Color,
+    pub metallic: f64,
+    pub roughness: f64,
+    pub reflectance: f64,
+}
+
+impl MetallicRoughness {
+    pub fn new(base_color: Color, metallic: f64, roughness: f64, ior: f64) -> MetallicRoughness {
+        MetallicRoughness {
+            base_color,
+            metallic: metallic.clamp(0., 1.),
+            // Fully smooth surfaces would need a delta distribution, which
+            // point lights can never hit.
+            roughness: roughness.clamp(0.02, 1.),
+            reflectance: ((ior - 1.) / (ior + 1.)).powi(2),
+        }
+    }
+
+    pub fn f0(&self) -> Color {
+        let dielectric = Color::new(self.reflectance, self.reflectance, self.reflectance);
+        dielectric * (1. - self.metallic) + self.base_color * self.metallic
+    }
+
+    pub fn diffuse_color(&self) -> Color {
+        self.base_color * (1. - self.metallic)
+    }
+
+    pub fn diffuse(&self, n: Vector, v: Vector, l: Vector) -> Color {
+        let n_dot_l = n.dot(&l);
+        if n_dot_l <= 0. || n.dot(&v) <= 0. {
+            return Color::new(0., 0., 0.);
+        }
+        let h = (v + l).normalized();
+        let f = schlick(self.f0(), v.dot(&h));
+        self.diffuse_color() * (Color::new(1., 1., 1.) - f) * n_dot_l
+    }
+
+    // Scaled by π to match the diffuse terms
+    pub fn specular(&self, n: Vector, v: Vector, l: Vector) -> Color {
+        let n_dot_l = n.dot(&l);
+        let n_dot_v = n.dot(&v);
+        if n_dot_l <= 0. || n_dot_v <= 0. {
+            return Color::new(0., 0., 0.);
+        }
+        let h = (v + l).normalized();
+        let alpha = self.roughness.powi(2);
+        let d = ggx(n.dot(&h), alpha);
+        let g = smith(n_dot_l, alpha) * smith(n_dot_v, alpha);
+        let f = schlick(self.f0(), v.dot(&h));
+        f * (PI * d * g / (4. * n_dot_v))
+    }
+}
+
+fn ggx(n_dot_h: f64, alpha: f64) -> f64 {
+    let a2 = alpha.powi(2);
+    a2 / (PI * (n_dot_h.powi(2) * (a2 - 1.) + 1.).powi(2))
+}
+
+fn smith(n_dot_x: f64, alpha: f64) -> f64 {
+    let a2 = alpha.powi(2);
+    2. * n_dot_x / (n_dot_x + (a2 + (1. - a2) * n_dot_x.powi(2)).sqrt())
+}
+
+fn schlick(f0: Color, cos_theta: f64) -> Color {
+    let k = (1. - cos_theta.clamp(0., 1.)).powi(5);
+    f0 + (Color::new(1., 1., 1.) - f0) * k
+}
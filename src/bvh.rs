@@ -66,7 +66,7 @@ impl Intersect for BoundingBox {
     }
 
     fn get_ray_intersection(&self, ray: Ray) -> Hit {
-        let shape: Option<Intersectable> = Some(Arc::new(self.clone()));
+        let shape: Option<Intersectable> = None;
         let mut hit = Hit {
             t: TMAX,
             shape,
@@ -146,6 +146,7 @@ pub struct BVHNode {
     bbox: BoundingBox,
     left: Option<Intersectable>,
     right: Option<Intersectable>,
+    leaf: bool,
 }
 
 impl BVHNode {
@@ -154,6 +155,7 @@ impl BVHNode {
         let left: Option<Intersectable>;
         let right: Option<Intersectable>;
         let mut bbox: BoundingBox;
+        let leaf = n <= 2;
         if n == 1 {
             left = Some(Arc::clone(&shapes[0]));
             right = None;
@@ -233,7 +235,20 @@ impl BVHNode {
                 right = None;
             }
         }
-        BVHNode { left, right, bbox }
+        BVHNode {
+            left,
+            right,
+            bbox,
+            leaf,
+        }
+    }
+
+    fn get_child_intersection(&self, child: &Intersectable, ray: Ray) -> Hit {
+        if self.leaf {
+            get_opaque_intersection(child, ray)
+        } else {
+            child.get_ray_intersection(ray)
+        }
     }
 }
 
@@ -257,10 +272,10 @@ impl Intersect for BVHNode {
             hit_left.hit = false;
             hit_right.hit = false;
             if let Some(ref s) = self.left {
-                hit_left = s.get_ray_intersection(ray);
+                hit_left = self.get_child_intersection(s, ray);
             }
             if let Some(ref s) = self.right {
-                hit_right = s.get_ray_intersection(ray);
+                hit_right = self.get_child_intersection(s, ray);
             }
             if hit_left.hit && hit_right.hit {
                 if hit_left.t < hit_right.t {
@@ -284,3 +299,114 @@ impl Intersect for BVHNode {
         self.bbox.get_extents()
     }
 }
+
+// Hits on cutouts are skipped by continuing the ray from them
+fn get_opaque_intersection(shape: &Intersectable, ray: Ray) -> Hit {
+    let mut hit = shape.get_ray_intersection(ray);
+    let mut t = 0.;
+    while hit.hit {
+        let point = hit.ray.find_point(hit.t);
+        t += hit.t;
+        if shape.is_opaque_at(point) {
+            hit.shape = Some(Arc::clone(shape));
+            hit.ray = ray;
+            hit.t = t;
+            return hit;
+        }
+        hit = shape.get_ray_intersection(Ray {
+            pos: point,
+            dir: ray.dir,
+        });
+    }
+    hit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color::Color;
+    use intersect::{Sphere, Triangle};
+    use scene::Material;
+    use texture::{Pattern, Procedural, Space};
+    use testing::assert_close;
+
+    // Tilted off the z axis, which bounding boxes don't handle
+    fn ray_up_z(x: f64, y: f64) -> Ray {
+        Ray {
+            pos: Vector { x, y, z: -5. },
+            dir: Vector {
+                x: 0.01,
+                y: 0.02,
+                z: 1.,
+            }
+            .normalized(),
+        }
+    }
+
+    fn square(z: f64, opacity: f64) -> Vec<Intersectable> {
+        let corner = |x, y| Vector { x, y, z };
+        let mat = Material {
+            opacity,
+            ..Material::new()
+        };
+        vec![
+            Arc::new(Triangle {
+                v1: corner(-1., -1.),
+                v2: corner(1., -1.),
+                v3: corner(1., 1.),
+                uvs: None,
+                mat: mat.clone(),
+            }),
+            Arc::new(Triangle {
+                v1: corner(-1., -1.),
+                v2: corner(1., 1.),
+                v3: corner(-1., 1.),
+                uvs: None,
+                mat,
+            }),
+        ]
+    }
+
+    #[test]
+    fn skips_cutouts_within_a_shape() {
+        // Opaque where the checker is white, which is only the far half
+        let mut mat = Material::new();
+        mat.textures.opacity = Some(Arc::new(Procedural::new(
+            Pattern::Checker,
+            Color::new(1., 1., 1.),
+            Color::new(0., 0., 0.),
+            10.,
+            Space::Object,
+        )));
+        let sphere: Intersectable = Arc::new(Sphere {
+            pos: Vector::new(),
+            r: 1.,
+            mat,
+        });
+        let ray = ray_up_z(0.1, 0.1);
+        let hit = get_opaque_intersection(&sphere, ray);
+
+        assert!(hit.hit);
+        assert!(hit.shape.is_some());
+        let point = ray.find_point(hit.t);
+        assert!(point.z > 0.);
+        assert_close(point.magnitude(), 1.);
+    }
+
+    #[test]
+    fn rays_pass_through_cut_away_shapes() {
+        let mut shapes = square(-1., 0.);
+        shapes.extend(square(1., 1.));
+        shapes.extend(square(2., 1.));
+        let bvh = BVHNode::new(&shapes, XAxis);
+
+        let ray = ray_up_z(0.5, -0.2);
+        let hit = bvh.get_ray_intersection(ray);
+        assert!(hit.hit);
+        assert_close(ray.find_point(hit.t).z, 1.);
+        assert_eq!(hit.shape.unwrap().get_material().opacity, 1.);
+
+        let bvh = BVHNode::new(&square(0., 0.), XAxis);
+        assert!(!bvh.get_ray_intersection(ray_up_z(0.5, -0.2)).hit);
+    }
+}
@@ -26,6 +26,19 @@ impl Color {
     pub fn is_not_black(&self) -> bool {
         self.r != 0. || self.g != 0. || self.b != 0.
     }
+
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    // Beer–Lambert law
+    pub fn transmittance(&self, distance: f64) -> Color {
+        Color {
+            r: (-self.r * distance).exp(),
+            g: (-self.g * distance).exp(),
+            b: (-self.b * distance).exp(),
+        }
+    }
 }
 
 impl ops::Add for Color {
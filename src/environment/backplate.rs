@@ -0,0 +1,18 @@
+use color::Color;
+use texture::{ImageTexture, Wrap};
+
+pub struct Backplate {
+    image: ImageTexture,
+}
+
+impl Backplate {
+    pub fn load(filename: &str) -> Backplate {
+        Backplate {
+            image: ImageTexture::load_srgb(filename, Wrap::Clamp),
+        }
+    }
+
+    pub fn color(&self, s: f64, t: f64) -> Color {
+        self.image.sample(s, t)
+    }
+}
@@ -0,0 +1,16 @@
+use color::Color;
+use environment::Environment;
+use math::Vector;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Gradient {
+    pub horizon: Color,
+    pub zenith: Color,
+}
+
+impl Environment for Gradient {
+    fn radiance(&self, dir: Vector) -> Color {
+        let t = dir.normalized().y.max(0.);
+        self.horizon * (1. - t) + self.zenith * t
+    }
+}
@@ -0,0 +1,114 @@
+extern crate image;
+
+use std::f64::consts::PI;
+
+use color::Color;
+use environment::Environment;
+use math::Vector;
+use pfm;
+
+// Latitude-longitude image with +y at the top row
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    row_cdf: Vec<f64>,
+    col_cdfs: Vec<Vec<f64>>,
+}
+
+impl EnvironmentMap {
+    pub fn load(filename: &str, intensity: f64) -> EnvironmentMap {
+        let (width, height, pixels) = if filename.to_lowercase().ends_with(".pfm") {
+            pfm::read(filename).expect("Error loading environment map")
+        } else {
+            let img = image::open(filename)
+                .expect("Error loading environment map")
+                .to_rgb32f();
+            let pixels = img
+                .pixels()
+                .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+                .collect();
+            (img.width() as usize, img.height() as usize, pixels)
+        };
+        let pixels = pixels.into_iter().map(|c| c * intensity).collect();
+        EnvironmentMap::new(width, height, pixels)
+    }
+
+    fn new(width: usize, height: usize, pixels: Vec<Color>) -> EnvironmentMap {
+        // Weight each pixel by the solid angle it covers, which shrinks
+        // toward the poles.
+        let mut row_cdf = vec![0.];
+        let mut col_cdfs = Vec::with_capacity(height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            let mut cdf = vec![0.];
+            for x in 0..width {
+                let sum = cdf[x] + pixels[y * width + x].luminance().max(0.) * sin_theta;
+                cdf.push(sum);
+            }
+            row_cdf.push(row_cdf[y] + cdf[width]);
+            col_cdfs.push(cdf);
+        }
+
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            row_cdf,
+            col_cdfs,
+        }
+    }
+
+    pub fn sample(&self, (a, b): (f64, f64)) -> Option<(Vector, f64)> {
+        let total = self.row_cdf[self.height];
+        if total <= 0. {
+            return None;
+        }
+
+        let (y, fy) = find_interval(&self.row_cdf, a * total);
+        let row = &self.col_cdfs[y];
+        let (x, fx) = find_interval(row, b * row[self.width]);
+
+        let theta = PI * (y as f64 + fy) / self.height as f64;
+        let phi = 2. * PI * (x as f64 + fx) / self.width as f64 - PI;
+        let dir = Vector {
+            x: theta.sin() * phi.cos(),
+            y: theta.cos(),
+            z: theta.sin() * phi.sin(),
+        };
+
+        let sin_theta = theta.sin();
+        if sin_theta <= 0. {
+            return None;
+        }
+        let cell_probability = (row[x + 1] - row[x]) / total;
+        let pdf = cell_probability * (self.width * self.height) as f64
+            / (2. * PI * PI * sin_theta);
+        Some((dir, pdf))
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, dir: Vector) -> Color {
+        let dir = dir.normalized();
+        let theta = dir.y.clamp(-1., 1.).acos();
+        let phi = dir.z.atan2(dir.x);
+        let x = ((phi + PI) / (2. * PI) * self.width as f64) as usize % self.width;
+        let y = ((theta / PI * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+}
+
+fn find_interval(cdf: &[f64], value: f64) -> (usize, f64) {
+    let i = cdf
+        .partition_point(|&c| c <= value)
+        .saturating_sub(1)
+        .min(cdf.len() - 2);
+    let width = cdf[i + 1] - cdf[i];
+    let offset = if width > 0. {
+        ((value - cdf[i]) / width).clamp(0., 1.)
+    } else {
+        0.5
+    };
+    (i, offset)
+}
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use color::Color;
+use math::Vector;
+
+pub use self::backplate::Backplate;
+pub use self::gradient::Gradient;
+pub use self::map::EnvironmentMap;
+pub use self::sky::Sky;
+
+mod backplate;
+mod gradient;
+mod map;
+mod sky;
+
+pub trait Environment {
+    fn radiance(&self, dir: Vector) -> Color;
+}
+
+pub type Environmental = Arc<dyn Environment>;
+
+impl Environment for Color {
+    fn radiance(&self, _dir: Vector) -> Color {
+        *self
+    }
+}
@@ -0,0 +1,121 @@
+use std::f64::consts::PI;
+
+use color::Color;
+use environment::Environment;
+use math::Vector;
+
+// Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight"
+#[derive(Copy, Clone, Debug)]
+pub struct Sky {
+    pub sun: Vector,
+    pub turbidity: f64,
+    pub intensity: f64,
+    perez_y: [f64; 5],
+    perez_x: [f64; 5],
+    perez_luminance: [f64; 5],
+    zenith_x: f64,
+    zenith_y: f64,
+}
+
+impl Sky {
+    // Angles in radians, azimuth from +z toward +x
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64, intensity: f64) -> Sky {
+        let t = turbidity;
+        let sun = Vector {
+            x: elevation.cos() * azimuth.sin(),
+            y: elevation.sin(),
+            z: elevation.cos() * azimuth.cos(),
+        };
+
+        // Distribution coefficients for luminance and chromaticity
+        let perez_luminance = [
+            0.1787 * t - 1.4630,
+            -0.3554 * t + 0.4275,
+            -0.0227 * t + 5.3251,
+            0.1206 * t - 2.5771,
+            -0.0670 * t + 0.3703,
+        ];
+        let perez_x = [
+            -0.0193 * t - 0.2592,
+            -0.0665 * t + 0.0008,
+            -0.0004 * t + 0.2125,
+            -0.0641 * t - 0.8989,
+            -0.0033 * t + 0.0452,
+        ];
+        let perez_y = [
+            -0.0167 * t - 0.2608,
+            -0.0950 * t + 0.0092,
+            -0.0079 * t + 0.2102,
+            -0.0441 * t - 1.6537,
+            -0.0109 * t + 0.0529,
+        ];
+
+        let theta_s = PI / 2. - elevation;
+        let ts = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.];
+        let dot = |a: [f64; 4]| a[0] * ts[0] + a[1] * ts[1] + a[2] * ts[2] + a[3] * ts[3];
+        let zenith_x = t.powi(2) * dot([0.00166, -0.00375, 0.00209, 0.])
+            + t * dot([-0.02903, 0.06377, -0.03202, 0.00394])
+            + dot([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t.powi(2) * dot([0.00275, -0.00610, 0.00317, 0.])
+            + t * dot([-0.04214, 0.08970, -0.04153, 0.00516])
+            + dot([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        Sky {
+            sun,
+            turbidity,
+            intensity,
+            perez_y,
+            perez_x,
+            perez_luminance,
+            zenith_x,
+            zenith_y,
+        }
+    }
+
+    pub fn sun_color(&self) -> Color {
+        let theta_s = self.sun.y.clamp(0., 1.).acos();
+        let degrees = theta_s * 180. / PI;
+        let air_mass = 1. / (theta_s.cos() + 0.15 * (93.885 - degrees).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |lambda_nm: f64| {
+            let lambda = lambda_nm / 1000.;
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+            rayleigh * aerosol
+        };
+        Color::new(transmittance(680.), transmittance(550.), transmittance(440.))
+    }
+}
+
+impl Environment for Sky {
+    fn radiance(&self, dir: Vector) -> Color {
+        let mut dir = dir.normalized();
+        dir.y = dir.y.max(0.001);
+        let dir = dir.normalized();
+
+        let cos_theta = dir.y;
+        let gamma = dir.dot(&self.sun).clamp(-1., 1.).acos();
+        let theta_s = self.sun.y.clamp(0., 1.).acos();
+
+        // Each quantity is its zenith value scaled by the Perez distribution
+        // relative to the zenith.
+        let relative = |p: &[f64; 5]| perez(p, cos_theta, gamma) / perez(p, 1., theta_s);
+        let luminance = self.intensity * relative(&self.perez_luminance);
+        let x = self.zenith_x * relative(&self.perez_x);
+        let y = self.zenith_y * relative(&self.perez_y);
+
+        // xyY to linear sRGB
+        let big_x = x / y * luminance;
+        let big_z = (1. - x - y) / y * luminance;
+        Color::new(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        )
+    }
+}
+
+fn perez(p: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1. + p[0] * (p[1] / cos_theta).exp())
+        * (1. + p[2] * (p[3] * gamma).exp() + p[4] * gamma.cos().powi(2))
+}
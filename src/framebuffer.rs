@@ -0,0 +1,204 @@
+extern crate image;
+
+use std::path::Path;
+
+use color::Color;
+use pfm;
+use tonemap::OutputTransform;
+
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+    pub alpha: Option<Vec<f64>>,
+    pub premultiplied: bool,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Color::new(0., 0., 0.); (width * height) as usize],
+            alpha: None,
+            premultiplied: false,
+        }
+    }
+
+    pub fn with_alpha(width: u32, height: u32, premultiplied: bool) -> Framebuffer {
+        Framebuffer {
+            alpha: Some(vec![0.; (width * height) as usize]),
+            premultiplied,
+            ..Framebuffer::new(width, height)
+        }
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: Color, alpha: f64) {
+        let i = (y * self.width + x) as usize;
+        self.pixels[i] = color;
+        if let Some(ref mut a) = self.alpha {
+            a[i] = alpha;
+        }
+    }
+
+    fn rgba(&self, x: u32, y: u32, premultiply: bool) -> (Color, f64) {
+        let i = (y * self.width + x) as usize;
+        let c = self.pixels[i];
+        let a = match self.alpha {
+            Some(ref alpha) => alpha[i],
+            None => return (c, 1.),
+        };
+        match (self.premultiplied, premultiply) {
+            (false, true) => (c * a, a),
+            (true, false) if a > 0. => (c / a, a),
+            _ => (c, a),
+        }
+    }
+
+    pub fn save(&self, filename: &str, transform: &OutputTransform) {
+        let path = Path::new(filename);
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+        match extension.as_deref() {
+            Some("pfm") => {
+                let pixels: Vec<Color> = (0..self.height)
+                    .flat_map(|y| (0..self.width).map(move |x| self.rgba(x, y, false).0))
+                    .collect();
+                pfm::write(filename, self.width as usize, self.height as usize, &pixels)
+                    .expect("Error saving image");
+            }
+            Some("exr") if self.alpha.is_some() => {
+                let img = image::Rgba32FImage::from_fn(self.width, self.height, |x, y| {
+                    let (c, a) = self.rgba(x, y, true);
+                    image::Rgba([c.r as f32, c.g as f32, c.b as f32, a as f32])
+                });
+                img.save(path).expect("Error saving image");
+            }
+            Some("exr") | Some("hdr") => {
+                let img = image::Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+                    let (c, _) = self.rgba(x, y, false);
+                    image::Rgb([c.r as f32, c.g as f32, c.b as f32])
+                });
+                img.save(path).expect("Error saving image");
+            }
+            Some("png") | Some("tga") | Some("tif") | Some("tiff") | Some("webp")
+                if self.alpha.is_some() =>
+            {
+                let img = image::RgbaImage::from_fn(self.width, self.height, |x, y| {
+                    let (c, a) = self.rgba(x, y, false);
+                    let [r, g, b] = transform.apply(c).to_u8_array();
+                    image::Rgba([r, g, b, (a.clamp(0., 1.) * 255.).round() as u8])
+                });
+                img.save(path).expect("Error saving image");
+            }
+            _ => {
+                let img = image::RgbImage::from_fn(self.width, self.height, |x, y| {
+                    let (c, _) = self.rgba(x, y, false);
+                    image::Rgb(transform.apply(c).to_u8_array())
+                });
+                img.save(path).expect("Error saving image");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{assert_close, TempFile};
+
+    fn half_covered(premultiplied: bool) -> Framebuffer {
+        let mut fb = Framebuffer::with_alpha(2, 2, premultiplied);
+        let c = if premultiplied {
+            Color::new(0.4, 0.2, 0.)
+        } else {
+            Color::new(0.8, 0.4, 0.)
+        };
+        for y in 0..2 {
+            for x in 0..2 {
+                fb.set(x, y, c, 0.5);
+            }
+        }
+        fb
+    }
+
+    #[test]
+    fn writes_straight_alpha_to_png() {
+        let file = TempFile::new("alpha.png");
+        half_covered(true).save(file.name(), &OutputTransform::new());
+        let img = image::open(&file.path).unwrap().to_rgba8();
+        assert_eq!(img.get_pixel(1, 1).0, [204, 102, 0, 128]);
+    }
+
+    #[test]
+    fn drops_alpha_for_formats_without_it() {
+        let file = TempFile::new("alpha.jpg");
+        half_covered(true).save(file.name(), &OutputTransform::new());
+        let img = image::open(&file.path).unwrap();
+        assert!(!img.color().has_alpha());
+        let [r, g, b] = img.to_rgb8().get_pixel(0, 0).0;
+        assert!((r as i32 - 204).abs() <= 2 && (g as i32 - 102).abs() <= 2 && b <= 2);
+    }
+
+    #[test]
+    fn writes_premultiplied_alpha_to_exr() {
+        let file = TempFile::new("alpha.exr");
+        half_covered(false).save(file.name(), &OutputTransform::new());
+        let img = image::open(&file.path).unwrap().to_rgba32f();
+        let [r, g, b, a] = img.get_pixel(0, 1).0;
+        assert_close(r as f64, 0.4);
+        assert_close(g as f64, 0.2);
+        assert_close(b as f64, 0.);
+        assert_close(a as f64, 0.5);
+    }
+
+    fn bright() -> Framebuffer {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.set(0, 0, Color::new(4., 0.5, 0.), 1.);
+        fb.set(1, 0, Color::new(0.25, 1.5, 2.), 1.);
+        fb
+    }
+
+    #[test]
+    fn keeps_values_above_one_in_float_formats() {
+        for name in &["bright.pfm", "bright.exr", "bright.hdr"] {
+            let file = TempFile::new(name);
+            bright().save(file.name(), &OutputTransform::new());
+            let pixels: Vec<Color> = if name.ends_with(".pfm") {
+                pfm::read(file.name()).unwrap().2
+            } else {
+                let img = image::open(&file.path).unwrap().to_rgb32f();
+                img.pixels()
+                    .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+                    .collect()
+            };
+            // Radiance HDR shares one exponent between the channels
+            let tolerance = if name.ends_with(".hdr") { 0.05 } else { 1e-6 };
+            let expected = [(4., 0.5, 0.), (0.25, 1.5, 2.)];
+            for (c, e) in pixels.iter().zip(&expected) {
+                assert!(
+                    (c.r - e.0).abs() <= tolerance
+                        && (c.g - e.1).abs() <= tolerance
+                        && (c.b - e.2).abs() <= tolerance,
+                    "{} has {:?}, not {:?}",
+                    name,
+                    c,
+                    e
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clamps_8_bit_formats_through_the_transform() {
+        let file = TempFile::new("bright.png");
+        let mut transform = OutputTransform::new();
+        transform.exposure = -1.;
+        bright().save(file.name(), &transform);
+        let img = image::open(&file.path).unwrap().to_rgb8();
+        assert_eq!(img.get_pixel(0, 0).0, [255, 63, 0]);
+        assert_eq!(img.get_pixel(1, 0).0, [31, 191, 255]);
+    }
+}
@@ -0,0 +1,70 @@
+use std::f64;
+
+use color::Color;
+use scene::Scene;
+use math::{Ray, RayDifferential, Vector};
+use math::sampling::{cosine_hemisphere, stratified_samples};
+use integrator::{get_ray_intersection, Integrator};
+
+#[derive(Copy, Clone, Debug)]
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new() -> AmbientOcclusion {
+        AmbientOcclusion {
+            samples: 16,
+            max_distance: f64::INFINITY,
+        }
+    }
+
+    pub fn visibility(&self, scene: &Scene, point: Vector, n: Vector) -> f64 {
+        if self.samples == 0 {
+            return 1.;
+        }
+        let open = stratified_samples(self.samples)
+            .into_iter()
+            .filter(|&sample| {
+                let ray = Ray {
+                    pos: point,
+                    dir: cosine_hemisphere(sample, n),
+                };
+                let hit = get_ray_intersection(scene, ray);
+                !hit.hit || hit.t * ray.dir.magnitude() > self.max_distance
+            })
+            .count();
+        open as f64 / self.samples as f64
+    }
+}
+
+pub struct OcclusionPass;
+
+impl Integrator for OcclusionPass {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        _differential: Option<RayDifferential>,
+    ) -> (Color, bool) {
+        let hit = get_ray_intersection(scene, ray);
+        if !hit.hit {
+            return (Color::new(1., 1., 1.), false);
+        }
+
+        let shape = hit.shape.unwrap();
+        let point_hit = hit.ray.find_point(hit.t);
+        let v = (hit.ray.pos - point_hit).normalized();
+        let mut n = shape.surface_normal(point_hit, v);
+        if v.dot(&n) < 0. {
+            n *= -1.;
+        }
+
+        let ao = scene
+            .ambient_occlusion
+            .unwrap_or_else(AmbientOcclusion::new);
+        let visibility = ao.visibility(scene, point_hit, n);
+        (Color::new(visibility, visibility, visibility), true)
+    }
+}
@@ -0,0 +1,214 @@
+use color::Color;
+use scene::{Material, Scene};
+use math::{Ray, RayDifferential, Vector};
+use intersect::{Hit, Intersect};
+
+pub use self::whitted::Whitted;
+pub use self::path::PathTracer;
+pub use self::ao::{AmbientOcclusion, OcclusionPass};
+
+mod whitted;
+mod path;
+mod ao;
+
+pub trait Integrator {
+    // Also tells whether the ray hit a surface or visible light
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        differential: Option<RayDifferential>,
+    ) -> (Color, bool);
+}
+
+pub fn by_name(name: &str) -> Option<Box<dyn Integrator>> {
+    match name {
+        "whitted" => Some(Box::new(Whitted)),
+        "path" => Some(Box::new(PathTracer)),
+        "ao" => Some(Box::new(OcclusionPass)),
+        _ => None,
+    }
+}
+
+pub fn direct_lighting(
+    scene: &Scene,
+    point_hit: Vector,
+    v: Vector,
+    n: Vector,
+    mat: &Material,
+) -> Color {
+    let mut color = Color::new(0., 0., 0.);
+    for light in scene.lights.iter() {
+        for sample in light.samples(point_hit) {
+            let reflected = mat.diffuse(n, v, sample.l) + mat.specular(n, v, sample.l);
+            if !reflected.is_not_black() {
+                continue;
+            }
+
+            // Check shadow
+            let transmission = shadow_transmission(scene, point_hit, sample.l, sample.distance);
+            if !transmission.is_not_black() {
+                // printf("Shadowing\n");
+                continue;
+            }
+
+            color += reflected * sample.intensity * transmission;
+        }
+    }
+    color
+}
+
+pub fn shadow_transmission(scene: &Scene, point: Vector, l: Vector, light_distance: f64) -> Color {
+    let mut transmission = Color::new(1., 1., 1.);
+    let mut pos = point;
+    let mut traveled = 0.;
+    let mut entered_at: Option<f64> = None;
+    loop {
+        let shadow = Ray { pos, dir: l };
+        let shadow_hit = get_ray_intersection(scene, shadow);
+        if !shadow_hit.hit {
+            return transmission;
+        }
+        let p = shadow_hit.ray.find_point(shadow_hit.t);
+        traveled += (p - pos).magnitude();
+        if traveled >= light_distance {
+            return transmission;
+        }
+
+        let shape = shadow_hit.shape.unwrap();
+        let mat = shape.material_at(p, l * -1., None);
+        if !mat.trs.is_not_black() {
+            return Color::new(0., 0., 0.);
+        }
+        transmission = transmission * mat.trs;
+
+        if shape.is_entering(p, l * -1.) {
+            entered_at = Some(traveled);
+        } else {
+            // Leaving the object, so absorb over the distance spent inside
+            let start = entered_at.take().unwrap_or(0.);
+            transmission = transmission * mat.absorption.transmittance(traveled - start);
+        }
+        pos = p;
+    }
+}
+
+// Camera rays, at depth 1, see the backplate or camera background
+pub fn get_background(scene: &Scene, ray: Ray, current_depth: u32) -> Color {
+    if current_depth == 1 {
+        if let Some(ref backplate) = scene.backplate {
+            let (s, t) = get_canvas_position(scene, ray.dir);
+            return backplate.color(s, t);
+        }
+        if let Some(ref environment) = scene.camera_environment {
+            return environment.radiance(ray.dir);
+        }
+    }
+    match scene.environment {
+        Some(ref environment) => environment.radiance(ray.dir),
+        None => scene.background,
+    }
+}
+
+pub fn get_canvas_position(scene: &Scene, dir: Vector) -> (f64, f64) {
+    let camera = &scene.camera;
+    let canvas = &scene.canvas;
+    let k = canvas.depth / -dir.dot(&camera.dir);
+    let u = dir.dot(&camera.right) * k;
+    let v = dir.dot(&camera.up) * k;
+    (
+        (u - canvas.left) / scene.width as f64,
+        (v - canvas.bottom) / scene.height as f64,
+    )
+}
+
+pub fn get_emitter_intersection(scene: &Scene, ray: Ray) -> Option<(f64, Color)> {
+    scene
+        .lights
+        .iter()
+        .filter_map(|light| light.emitter_hit(ray))
+        .fold(
+            None,
+            |closest: Option<(f64, Color)>, (t, color)| match closest {
+                Some((closest_t, _)) if closest_t <= t => closest,
+                _ => Some((t, color)),
+            },
+        )
+}
+
+pub fn get_ray_intersection(scene: &Scene, ray: Ray) -> Hit {
+    scene.bvhroot.get_ray_intersection(ray)
+}
+
+pub fn fresnel(cos_i: f64, ior_i: f64, ior_t: f64) -> f64 {
+    let sin_t = ior_i / ior_t * (1. - cos_i.powi(2)).max(0.).sqrt();
+    if sin_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin_t.powi(2)).sqrt();
+    let r_parallel = (ior_t * cos_i - ior_i * cos_t) / (ior_t * cos_i + ior_i * cos_t);
+    let r_perpendicular = (ior_i * cos_i - ior_t * cos_t) / (ior_i * cos_i + ior_t * cos_t);
+    (r_parallel.powi(2) + r_perpendicular.powi(2)) / 2.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::assert_close;
+
+    #[test]
+    fn fresnel_at_normal_incidence() {
+        // ((n1 - n2) / (n1 + n2))^2 from either side
+        assert_close(fresnel(1., 1., 1.5), 0.04);
+        assert_close(fresnel(1., 1.5, 1.), 0.04);
+        assert_close(fresnel(1., 1.3, 1.3), 0.);
+    }
+
+    #[test]
+    fn fresnel_grows_toward_grazing_angles() {
+        let mut last = 0.;
+        for i in 0..10 {
+            let cos_i = 1. - i as f64 / 10.;
+            let kr = fresnel(cos_i, 1., 1.5);
+            assert!(kr >= last);
+            last = kr;
+        }
+        assert_close(fresnel(0., 1., 1.5), 1.);
+    }
+
+    #[test]
+    fn fresnel_reflects_everything_past_the_critical_angle() {
+        // The critical angle from glass into air is about 41.8 degrees
+        let critical = (1. / 1.5f64).asin();
+        assert!(fresnel((critical - 0.01).cos(), 1.5, 1.) < 1.);
+        assert_eq!(fresnel((critical + 0.01).cos(), 1.5, 1.), 1.);
+    }
+
+    #[test]
+    fn refraction_fails_under_total_internal_reflection() {
+        let n = Vector {
+            x: 0.,
+            y: 1.,
+            z: 0.,
+        };
+        let steep = Vector {
+            x: 0.5,
+            y: -1.,
+            z: 0.,
+        }
+        .normalized();
+        let shallow = Vector {
+            x: 1.,
+            y: -0.2,
+            z: 0.,
+        }
+        .normalized();
+        assert!(steep.refract(&n, 1.5).is_some());
+        assert!(shallow.refract(&n, 1.5).is_none());
+
+        // Snell's law holds for the refracted direction
+        let refracted = steep.refract(&n, 1.5).unwrap();
+        assert_close(refracted.x / refracted.magnitude(), 1.5 * steep.x);
+        assert!(refracted.y < 0.);
+    }
+}
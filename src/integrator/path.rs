@@ -0,0 +1,182 @@
+use color::Color;
+use scene::{Material, Scene};
+use math::{Ray, RayDifferential, Vector};
+use math::sampling::{cosine_hemisphere, uniform};
+use integrator::{
+    fresnel, get_background, get_emitter_intersection, get_ray_intersection, Integrator,
+};
+
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    // Paths are only ended by Russian roulette, so no bounce limit biases
+    // the estimate
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        differential: Option<RayDifferential>,
+    ) -> (Color, bool) {
+        let mut color = Color::new(0., 0., 0.);
+        // Only followed through mirrors and glass, since a diffuse bounce
+        // spreads the pixel out over the whole hemisphere
+        let mut differential = differential;
+        let mut throughput = Color::new(1., 1., 1.);
+        let mut ray = ray;
+        let mut medium = Color::new(0., 0., 0.);
+        // Lights are sampled directly for the diffuse part only, so they
+        // count unless the last bounce was diffuse
+        let mut count_lights = true;
+
+        let mut covered = false;
+
+        for bounce in 0.. {
+            let hit = get_ray_intersection(scene, ray);
+            let emitter = get_emitter_intersection(scene, ray);
+            if bounce == 0 {
+                covered = hit.hit || emitter.is_some();
+            }
+            if let Some((t, emitted)) = emitter {
+                if !hit.hit || t < hit.t {
+                    if count_lights {
+                        let transmittance = medium.transmittance(t * ray.dir.magnitude());
+                        color += throughput * emitted * transmittance;
+                    }
+                    break;
+                }
+            }
+            if !hit.hit {
+                if count_lights || !scene.environment_sampled {
+                    color += throughput * get_background(scene, ray, bounce + 1);
+                }
+                break;
+            }
+            throughput = throughput * medium.transmittance(hit.t * ray.dir.magnitude());
+
+            let shape = hit.shape.unwrap();
+            let point_hit = hit.ray.find_point(hit.t);
+            let v = (hit.ray.pos - point_hit).normalized();
+            let normal = shape.surface_normal(point_hit, v);
+            let traced =
+                differential.and_then(|d| d.footprint(point_hit, normal).map(|fp| (d, fp)));
+            let footprint = traced.map(|(_, fp)| fp);
+            let mat = shape.material_at(point_hit, v, footprint);
+            let mut n = shape.shading_normal(point_hit, v, &mat, footprint);
+            let entering = shape.is_entering(point_hit, v);
+
+            if count_lights || !mat.emission_sampled {
+                color += throughput * mat.emission;
+            }
+            color += throughput * diffuse_lighting(scene, point_hit, v, n, &mat);
+
+            let p_diffuse = mat.dif.luminance().max(0.);
+            let p_specular = if mat.mirror {
+                mat.spec.luminance().max(0.)
+            } else {
+                0.
+            };
+            let p_transmit = mat.trs.luminance().max(0.);
+            let total = p_diffuse + p_specular + p_transmit;
+            if total <= 0. {
+                break;
+            }
+
+            let choice = uniform() * total;
+            let dir = if choice < p_diffuse {
+                if v.dot(&n) < 0. {
+                    n *= -1.;
+                }
+                throughput = throughput * mat.dif * (total / p_diffuse);
+                count_lights = false;
+                differential = None;
+                cosine_hemisphere((uniform(), uniform()), n)
+            } else if choice < p_diffuse + p_specular {
+                throughput = throughput * mat.spec * (total / p_specular);
+                count_lights = true;
+                differential = traced.map(|(d, fp)| d.reflect(point_hit, fp, n));
+                v.reflect(&n)
+            } else {
+                let (ior_i, ior_r, refract_medium) = if entering {
+                    (1., mat.ior, mat.absorption)
+                } else {
+                    // Turn the normal to the inside if it points out like on
+                    // spheres
+                    if v.dot(&normal) < 0. {
+                        n *= -1.;
+                    }
+                    (mat.ior, 1., Color::new(0., 0., 0.))
+                };
+                throughput = throughput * mat.trs * (total / p_transmit);
+                count_lights = true;
+                match (v * -1.).refract(&n, ior_i / ior_r) {
+                    Some(refract_v) => {
+                        let kr = if mat.fresnel {
+                            fresnel(v.dot(&n), ior_i, ior_r)
+                        } else {
+                            0.
+                        };
+                        if uniform() < kr {
+                            differential = traced.map(|(d, fp)| d.reflect(point_hit, fp, n));
+                            v.reflect(&n)
+                        } else {
+                            medium = refract_medium;
+                            differential = traced
+                                .and_then(|(d, fp)| d.refract(point_hit, fp, n, ior_i / ior_r));
+                            refract_v
+                        }
+                    }
+                    None => {
+                        differential = traced.map(|(d, fp)| d.reflect(point_hit, fp, n));
+                        v.reflect(&n)
+                    }
+                }
+            };
+
+            // Russian roulette
+            if bounce >= 3 {
+                let q = throughput.r.max(throughput.g).max(throughput.b).min(0.95);
+                if uniform() >= q {
+                    break;
+                }
+                throughput /= q;
+            }
+
+            ray = Ray {
+                pos: point_hit,
+                dir,
+            };
+        }
+
+        (color, covered)
+    }
+}
+
+// Light reaching `point_hit` straight from the lights and scattered by the
+// diffuse part of `mat`. Mirror and glass bounces find lights themselves, so
+// anything in the way blocks the light here.
+fn diffuse_lighting(
+    scene: &Scene,
+    point_hit: Vector,
+    v: Vector,
+    n: Vector,
+    mat: &Material,
+) -> Color {
+    let mut color = Color::new(0., 0., 0.);
+    for light in scene.lights.iter() {
+        for sample in light.samples(point_hit) {
+            let reflected = mat.diffuse(n, v, sample.l);
+            if !reflected.is_not_black() {
+                continue;
+            }
+            let shadow = Ray {
+                pos: point_hit,
+                dir: sample.l,
+            };
+            let hit = get_ray_intersection(scene, shadow);
+            if !hit.hit || hit.t >= sample.distance {
+                color += reflected * sample.intensity;
+            }
+        }
+    }
+    color
+}
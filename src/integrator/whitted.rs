@@ -0,0 +1,188 @@
+use color::Color;
+use scene::Scene;
+use math::{Ray, RayDifferential};
+use intersect::Hit;
+use integrator::{
+    direct_lighting, fresnel, get_background, get_emitter_intersection, get_ray_intersection,
+    Integrator,
+};
+
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn radiance(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        differential: Option<RayDifferential>,
+    ) -> (Color, bool) {
+        self.trace(scene, ray, differential, 0, Color::new(0., 0., 0.))
+    }
+}
+
+impl Whitted {
+    // `medium` is the absorption coefficient of what the ray travels through
+    fn evaluate_ray_tree(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        differential: Option<RayDifferential>,
+        current_depth: u32,
+        medium: Color,
+    ) -> Color {
+        let (color, _) = self.trace(scene, ray, differential, current_depth, medium);
+        color
+    }
+
+    fn trace(
+        &self,
+        scene: &Scene,
+        ray: Ray,
+        differential: Option<RayDifferential>,
+        current_depth: u32,
+        medium: Color,
+    ) -> (Color, bool) {
+        let current_depth = current_depth + 1;
+        if current_depth > scene.max_depth + 1 {
+            return (get_background(scene, ray, current_depth), false);
+        }
+
+        let hit = get_ray_intersection(scene, ray);
+        if let Some((t, emitted)) = get_emitter_intersection(scene, ray) {
+            if !hit.hit || t < hit.t {
+                let transmittance = medium.transmittance(t * ray.dir.magnitude());
+                return (emitted * transmittance, true);
+            }
+        }
+
+        if hit.hit {
+            // println!("Ray hit something");
+            let distance = hit.t * ray.dir.magnitude();
+            let color = self.apply_lighting_model(scene, hit, differential, current_depth, medium)
+                * medium.transmittance(distance);
+            (color, true)
+        } else {
+            // println!("Ray missed");
+            (get_background(scene, ray, current_depth), false)
+        }
+    }
+
+    fn apply_lighting_model(
+        &self,
+        scene: &Scene,
+        hit: Hit,
+        differential: Option<RayDifferential>,
+        current_depth: u32,
+        medium: Color,
+    ) -> Color {
+        let mut color = Color::new(0., 0., 0.);
+        let shape = hit.shape.unwrap();
+        let point_hit = hit.ray.find_point(hit.t);
+        let v = (hit.ray.pos - point_hit).normalized();
+        let normal = shape.surface_normal(point_hit, v);
+        // How much of the surface the pixel covers, for texture filtering
+        let traced = differential.and_then(|d| d.footprint(point_hit, normal).map(|fp| (d, fp)));
+        let footprint = traced.map(|(_, fp)| fp);
+        let mat = shape.material_at(point_hit, v, footprint);
+
+        let mut n = shape.shading_normal(point_hit, v, &mat, footprint);
+        let entering = shape.is_entering(point_hit, v);
+
+        let mut ambient = mat.amb * scene.ambient_light;
+        if let Some(ao) = scene.ambient_occlusion {
+            if ambient.is_not_black() {
+                let facing = if v.dot(&n) < 0. { n * -1. } else { n };
+                ambient *= ao.visibility(scene, point_hit, facing);
+            }
+        }
+        color += ambient;
+        color += mat.emission;
+
+        // As in classic Whitted tracing, highlights are seen from the camera
+        // even on surfaces reached by reflected and refracted rays
+        let eye = (scene.camera.pos - point_hit).normalized();
+        color += direct_lighting(scene, point_hit, eye, n, &mat);
+
+        // Reflected rays stay in the current medium while refracted rays cross
+        // into the other one.
+        let (ior_i, ior_r, refract_medium) = if entering {
+            // Going into solid
+            (1., mat.ior, mat.absorption)
+        } else {
+            // Going out of solid, with the normal turned to the inside if
+            // it points out like on spheres
+            if v.dot(&normal) < 0. {
+                n *= -1.;
+            }
+            (mat.ior, 1., Color::new(0., 0., 0.))
+        };
+
+        let reflect = Ray {
+            pos: point_hit,
+            dir: v.reflect(&n),
+        };
+        let mirror = if mat.mirror {
+            mat.spec
+        } else {
+            Color::new(0., 0., 0.)
+        };
+        let refract = if mat.trs.is_not_black() {
+            (v * -1.).refract(&n, ior_i / ior_r).map(|dir| Ray {
+                pos: point_hit,
+                dir,
+            })
+        } else {
+            None
+        };
+        let reflect_differential = traced.map(|(d, fp)| d.reflect(point_hit, fp, n));
+        let refract_differential =
+            traced.and_then(|(d, fp)| d.refract(point_hit, fp, n, ior_i / ior_r));
+
+        if mat.fresnel && mat.trs.is_not_black() {
+            // Split the transmitted energy between the reflected and refracted
+            // rays, sending all of it to reflection under total internal
+            // reflection.
+            let kr = match refract {
+                Some(_) => fresnel(v.dot(&n), ior_i, ior_r),
+                None => 1.,
+            };
+            let reflected =
+                self.evaluate_ray_tree(scene, reflect, reflect_differential, current_depth, medium);
+            color += mirror * reflected + mat.trs * reflected * kr;
+            if let Some(refract) = refract {
+                color += mat.trs
+                    * self.evaluate_ray_tree(
+                        scene,
+                        refract,
+                        refract_differential,
+                        current_depth,
+                        refract_medium,
+                    )
+                    * (1. - kr);
+            }
+        } else {
+            if mirror.is_not_black() {
+                color += mirror
+                    * self.evaluate_ray_tree(
+                        scene,
+                        reflect,
+                        reflect_differential,
+                        current_depth,
+                        medium,
+                    );
+            }
+            if let Some(refract) = refract {
+                color += mat.trs
+                    * self.evaluate_ray_tree(
+                        scene,
+                        refract,
+                        refract_differential,
+                        current_depth,
+                        refract_medium,
+                    );
+            }
+        }
+
+        color
+    }
+}
@@ -1,5 +1,6 @@
 use scene::Material;
 use math::{Ray, Vector, TMAX};
+use texture::TexturePoint;
 
 use std::sync::Arc;
 
@@ -11,6 +12,7 @@ mod triangle;
 
 pub struct Hit {
     pub hit: bool,
+    // Filled in by the BVH so testing a ray doesn't copy the shape
     pub shape: Option<Intersectable>,
     pub ray: Ray,
     pub t: f64,
@@ -36,7 +38,80 @@ pub trait Intersect {
     fn surface_normal(&self, _point: Vector, _v: Vector) -> Vector {
         Vector::new()
     }
+    // The outside of closed shapes, whichever side it's seen from
+    fn front_normal(&self, point: Vector) -> Vector {
+        self.surface_normal(point, Vector::new())
+    }
+    fn texture_coordinates(&self, _point: Vector) -> (f64, f64) {
+        (0., 0.)
+    }
+    fn object_point(&self, point: Vector) -> Vector {
+        point
+    }
+    fn tangents(&self, point: Vector) -> (Vector, Vector) {
+        self.surface_normal(point, Vector::new()).perpendiculars()
+    }
+    fn texture_point(&self, point: Vector, footprint: Option<(Vector, Vector)>) -> TexturePoint {
+        let (dx, dy) = match footprint {
+            Some((dpdx, dpdy)) => {
+                let (dpdu, dpdv) = self.tangents(point);
+                (uv_change(dpdu, dpdv, dpdx), uv_change(dpdu, dpdv, dpdy))
+            }
+            None => ((0., 0.), (0., 0.)),
+        };
+        TexturePoint {
+            uv: self.texture_coordinates(point),
+            world: point,
+            object: self.object_point(point),
+            dx,
+            dy,
+        }
+    }
+    fn is_entering(&self, point: Vector, v: Vector) -> bool {
+        v.dot(&self.front_normal(point)) > 0.
+    }
+    fn material_at(
+        &self,
+        point: Vector,
+        v: Vector,
+        footprint: Option<(Vector, Vector)>,
+    ) -> Material {
+        let mut mat = self.get_material();
+        if v.dot(&self.front_normal(point)) < 0. {
+            if let Some(back) = mat.back.take() {
+                mat = *back;
+            }
+        }
+        mat.at(&self.texture_point(point, footprint))
+    }
+    fn shading_normal(
+        &self,
+        point: Vector,
+        v: Vector,
+        mat: &Material,
+        footprint: Option<(Vector, Vector)>,
+    ) -> Vector {
+        let n = self.surface_normal(point, v);
+        if mat.textures.normal.is_none() && mat.textures.bump.is_none() {
+            return n;
+        }
+        let (dpdu, dpdv) = self.tangents(point);
+        mat.shading_normal(n, dpdu, dpdv, &self.texture_point(point, footprint))
+    }
+    fn is_opaque_at(&self, _point: Vector) -> bool {
+        true
+    }
     fn get_extents(&self) -> [f64; 6];
 }
 
 pub type Intersectable = Arc<Intersect>;
+
+fn uv_change(dpdu: Vector, dpdv: Vector, dp: Vector) -> (f64, f64) {
+    let (a11, a12, a22) = (dpdu.dot(&dpdu), dpdu.dot(&dpdv), dpdv.dot(&dpdv));
+    let (b1, b2) = (dpdu.dot(&dp), dpdv.dot(&dp));
+    let det = a11 * a22 - a12 * a12;
+    if det.abs() < 1e-12 {
+        return (0., 0.);
+    }
+    ((a22 * b1 - a12 * b2) / det, (a11 * b2 - a12 * b1) / det)
+}
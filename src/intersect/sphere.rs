@@ -1,10 +1,10 @@
-use std::sync::Arc;
+use std::f64::consts::PI;
 
 use intersect::{Hit, Intersect, Intersectable};
 use math::{Ray, Vector, TMAX, TMIN};
-use scene::Material;
+use scene::{Material, Sides};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Sphere {
     pub pos: Vector,
     pub r: f64,
@@ -19,7 +19,7 @@ impl Intersect for Sphere {
     fn get_ray_intersection(&self, ray: Ray) -> Hit {
         // println!("Checking ray {:?}", ray);
         let t = TMAX;
-        let shape: Option<Intersectable> = Some(Arc::new(self.clone()));
+        let shape: Option<Intersectable> = None;
         let hit = false;
         let mut hit = Hit { t, shape, hit, ray };
 
@@ -40,6 +40,15 @@ impl Intersect for Sphere {
         let t_plus = a + disc.sqrt();
         let t_minus = a - disc.sqrt();
 
+        // Only the near side faces rays from outside
+        if self.mat.sides == Sides::Front {
+            if t_minus > TMIN {
+                hit.t = t_minus;
+                hit.hit = true;
+            }
+            return hit;
+        }
+
         if t_plus > TMIN && t_minus > TMIN {
             hit.t = t_plus.min(t_minus);
         } else if t_plus > TMIN {
@@ -56,13 +65,50 @@ impl Intersect for Sphere {
     }
 
     fn get_material(&self) -> Material {
-        self.mat
+        self.mat.clone()
+    }
+
+    fn is_opaque_at(&self, point: Vector) -> bool {
+        self.mat.is_opaque(|| self.texture_point(point, None))
     }
 
     fn surface_normal(&self, point: Vector, _v: Vector) -> Vector {
         (point - self.pos).normalized()
     }
 
+    fn object_point(&self, point: Vector) -> Vector {
+        point - self.pos
+    }
+
+    // Longitude and latitude around the y axis, with v = 1 at the top
+    fn texture_coordinates(&self, point: Vector) -> (f64, f64) {
+        let d = (point - self.pos).normalized();
+        let u = (d.z.atan2(d.x) + PI) / (2. * PI);
+        let v = 1. - d.y.clamp(-1., 1.).acos() / PI;
+        (u, v)
+    }
+
+    fn tangents(&self, point: Vector) -> (Vector, Vector) {
+        let d = point - self.pos;
+        let dpdu = Vector {
+            x: -d.z,
+            y: 0.,
+            z: d.x,
+        } * (2. * PI);
+        // Longitude is undefined at the poles
+        if dpdu.magnitude() < 1e-9 * self.r {
+            return d.perpendiculars();
+        }
+        let theta = (d.y / self.r).clamp(-1., 1.).acos();
+        let phi = d.z.atan2(d.x);
+        let dpdtheta = Vector {
+            x: theta.cos() * phi.cos(),
+            y: -theta.sin(),
+            z: theta.cos() * phi.sin(),
+        } * self.r;
+        (dpdu, dpdtheta * -PI)
+    }
+
     fn get_extents(&self) -> [f64; 6] {
         [
             self.pos.x - self.r,
@@ -74,3 +120,47 @@ impl Intersect for Sphere {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::assert_close;
+
+    fn sphere(sides: Sides) -> Sphere {
+        Sphere {
+            pos: Vector::new(),
+            r: 1.,
+            mat: Material {
+                sides,
+                ..Material::new()
+            },
+        }
+    }
+
+    fn ray_from(z: f64) -> Ray {
+        Ray {
+            pos: Vector { x: 0., y: 0., z },
+            dir: Vector {
+                x: 0.,
+                y: 0.,
+                z: 1.,
+            },
+        }
+    }
+
+    #[test]
+    fn front_only_spheres_are_hit_only_from_outside() {
+        let s = sphere(Sides::Front);
+        let hit = s.get_ray_intersection(ray_from(-5.));
+        assert!(hit.hit);
+        assert_close(hit.t, 4.);
+        assert!(!s.get_ray_intersection(ray_from(0.)).hit);
+    }
+
+    #[test]
+    fn two_sided_spheres_are_hit_from_inside() {
+        let hit = sphere(Sides::Both).get_ray_intersection(ray_from(0.));
+        assert!(hit.hit);
+        assert_close(hit.t, 1.);
+    }
+}
@@ -1,24 +1,38 @@
-use scene::Material;
+use scene::{Material, Sides};
 use math::{Matrix, Ray, Vector, TMAX, TMIN};
 use intersect::{Hit, Intersect, Intersectable};
 
-use std::sync::Arc;
-
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Triangle {
     pub v1: Vector,
     pub v3: Vector,
     pub v2: Vector,
+    pub uvs: Option<[(f64, f64); 3]>,
     pub mat: Material,
 }
 
+impl Triangle {
+    fn barycentric(&self, point: Vector) -> (f64, f64) {
+        let e1 = self.v2 - self.v1;
+        let e2 = self.v3 - self.v1;
+        let p = point - self.v1;
+        let (d11, d12, d22) = (e1.dot(&e1), e1.dot(&e2), e2.dot(&e2));
+        let (dp1, dp2) = (p.dot(&e1), p.dot(&e2));
+        let denom = d11 * d22 - d12 * d12;
+        (
+            (d22 * dp1 - d12 * dp2) / denom,
+            (d11 * dp2 - d12 * dp1) / denom,
+        )
+    }
+}
+
 impl Intersect for Triangle {
     fn center(&self) -> Vector {
         (self.v1 + self.v2 + self.v3) / 3.
     }
 
     fn get_ray_intersection(&self, ray: Ray) -> Hit {
-        let shape: Option<Intersectable> = Some(Arc::new(self.clone()));
+        let shape: Option<Intersectable> = None;
         let mut hit = Hit {
             t: TMAX,
             shape,
@@ -26,6 +40,10 @@ impl Intersect for Triangle {
             ray,
         };
 
+        if self.mat.sides == Sides::Front && ray.dir.dot(&self.front_normal(ray.pos)) >= 0. {
+            return hit;
+        }
+
         let m = Matrix {
             v: [self.v1 - self.v2, self.v1 - self.v3, hit.ray.dir],
         };
@@ -61,7 +79,11 @@ impl Intersect for Triangle {
     }
 
     fn get_material(&self) -> Material {
-        self.mat
+        self.mat.clone()
+    }
+
+    fn is_opaque_at(&self, point: Vector) -> bool {
+        self.mat.is_opaque(|| self.texture_point(point, None))
     }
 
     fn surface_normal(&self, _point: Vector, v: Vector) -> Vector {
@@ -77,6 +99,47 @@ impl Intersect for Triangle {
         }
     }
 
+    // Vertices wind counterclockwise around the front face
+    fn front_normal(&self, _point: Vector) -> Vector {
+        (self.v2 - self.v1).cross(&(self.v3 - self.v1)).normalized()
+    }
+
+    // Only sided or solid meshes tell their inside apart by winding
+    fn is_entering(&self, point: Vector, v: Vector) -> bool {
+        if self.mat.sides == Sides::Both && self.mat.back.is_none() {
+            return true;
+        }
+        v.dot(&self.front_normal(point)) > 0.
+    }
+
+    fn texture_coordinates(&self, point: Vector) -> (f64, f64) {
+        let (b, c) = self.barycentric(point);
+        match self.uvs {
+            Some([t1, t2, t3]) => {
+                let a = 1. - b - c;
+                (
+                    t1.0 * a + t2.0 * b + t3.0 * c,
+                    t1.1 * a + t2.1 * b + t3.1 * c,
+                )
+            }
+            None => (b, c),
+        }
+    }
+
+    fn tangents(&self, _point: Vector) -> (Vector, Vector) {
+        let e1 = self.v2 - self.v1;
+        let e2 = self.v3 - self.v1;
+        if let Some([t1, t2, t3]) = self.uvs {
+            let (du1, dv1) = (t2.0 - t1.0, t2.1 - t1.1);
+            let (du2, dv2) = (t3.0 - t1.0, t3.1 - t1.1);
+            let det = du1 * dv2 - dv1 * du2;
+            if det.abs() > 1e-12 {
+                return ((e1 * dv2 - e2 * dv1) / det, (e2 * du1 - e1 * du2) / det);
+            }
+        }
+        (e1, e2)
+    }
+
     fn get_extents(&self) -> [f64; 6] {
         let x_min = self.v1.x.min(self.v2.x.min(self.v3.x));
         let x_max = self.v1.x.max(self.v2.x.max(self.v3.x));
@@ -87,3 +150,59 @@ impl Intersect for Triangle {
         [x_min, x_max, y_min, y_max, z_min, z_max]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Winds counterclockwise seen from +z
+    fn triangle(sides: Sides) -> Triangle {
+        Triangle {
+            v1: Vector::new(),
+            v2: Vector {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            },
+            v3: Vector {
+                x: 0.,
+                y: 1.,
+                z: 0.,
+            },
+            uvs: None,
+            mat: Material {
+                sides,
+                ..Material::new()
+            },
+        }
+    }
+
+    fn ray_along_z(z: f64) -> Ray {
+        Ray {
+            pos: Vector {
+                x: 0.25,
+                y: 0.25,
+                z,
+            },
+            dir: Vector {
+                x: 0.,
+                y: 0.,
+                z: -z.signum(),
+            },
+        }
+    }
+
+    #[test]
+    fn front_only_triangles_cull_back_faces() {
+        let t = triangle(Sides::Front);
+        assert!(t.get_ray_intersection(ray_along_z(5.)).hit);
+        assert!(!t.get_ray_intersection(ray_along_z(-5.)).hit);
+    }
+
+    #[test]
+    fn two_sided_triangles_are_hit_from_both_sides() {
+        let t = triangle(Sides::Both);
+        assert!(t.get_ray_intersection(ray_along_z(5.)).hit);
+        assert!(t.get_ray_intersection(ray_along_z(-5.)).hit);
+    }
+}
@@ -4,9 +4,20 @@ mod light;
 mod intersect;
 mod math;
 mod bvh;
+mod environment;
+mod pfm;
+mod framebuffer;
+mod tonemap;
+mod integrator;
+mod brdf;
+mod texture;
+mod library;
+mod obj;
 pub mod raytracer;
+#[cfg(test)]
+mod testing;
 
-pub use self::raytracer::run;
+pub use self::raytracer::{run, run_with_integrator};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+
+use color::Color;
+use scene::{
+    parse_color, parse_diffuse_model, parse_sides, parse_specular_model, parse_texture, Material,
+};
+
+pub fn define_material<'a, I: Iterator<Item = &'a str>>(
+    params: I,
+    dir: &Path,
+    materials: &HashMap<String, Material>,
+) -> Material {
+    let params: Vec<(&str, &str)> = params
+        .map(|param| {
+            let mut kv = param.splitn(2, '=');
+            let key = kv.next().unwrap();
+            let value = kv
+                .next()
+                .unwrap_or_else(|| panic!("Material parameter {} has no value", key));
+            (key, value)
+        })
+        .collect();
+    let value = |key: &str| params.iter().find(|p| p.0 == key).map(|p| p.1);
+
+    let mut mat =
+        if value("base").is_some() || value("metallic").is_some() || value("roughness").is_some() {
+            Material::metallic_roughness(
+                value("base").map_or(Color::new(0.8, 0.8, 0.8), parse_value_color),
+                value("metallic").map_or(0., |v| v.parse().unwrap()),
+                value("roughness").map_or(0.5, |v| v.parse().unwrap()),
+                value("ior").map_or(1.5, |v| v.parse().unwrap()),
+            )
+        } else {
+            let dif = value("dif").map_or(Color::new(0.8, 0.8, 0.8), parse_value_color);
+            Material {
+                amb: dif,
+                dif,
+                spec: Color::new(0., 0., 0.),
+                trs: Color::new(0., 0., 0.),
+                ..Material::new()
+            }
+        };
+
+    for &(key, v) in &params {
+        match key {
+            "base" | "metallic" | "roughness" => {}
+            "amb" => mat.amb = parse_value_color(v),
+            "dif" => {
+                // The ambient color follows the diffuse one unless given
+                if value("amb").is_none() && mat.metallic_roughness.is_none() {
+                    mat.amb = parse_value_color(v);
+                }
+                mat.dif = parse_value_color(v);
+            }
+            "spec" => mat.spec = parse_value_color(v),
+            "ns" => mat.ns = v.parse().unwrap(),
+            "trs" => mat.trs = parse_value_color(v),
+            "ior" => mat.ior = v.parse().unwrap(),
+            "fresnel" => mat.fresnel = v != "0" && v != "false",
+            "absorption" => mat.absorption = parse_value_color(v),
+            "emission" => mat.emission = parse_value_color(v),
+            "bump_scale" => mat.bump_scale = v.parse().unwrap(),
+            "opacity" => mat.opacity = v.parse().unwrap(),
+            "alpha_cutoff" => mat.alpha_cutoff = v.parse().unwrap(),
+            "sides" => mat.sides = parse_sides(v),
+            "back" => {
+                let back = materials
+                    .get(v)
+                    .unwrap_or_else(|| panic!("Unknown material {}", v));
+                mat.back = Some(Box::new(back.clone()));
+            }
+            "diffuse_model" => mat.diffuse_model = parse_diffuse_model(&mut v.split(',')),
+            "specular_model" => mat.specular_model = parse_specular_model(&mut v.split(',')),
+            _ if key.ends_with("_texture") => {
+                let channel = &key[..key.len() - "_texture".len()];
+                let texture = parse_texture(&mut v.split(','), channel, dir);
+                mat.textures.set(channel, texture);
+            }
+            _ => panic!("Unknown material parameter {}", key),
+        }
+    }
+    mat
+}
+
+pub fn load(filename: &Path, materials: &mut HashMap<String, Material>) {
+    let f = File::open(filename)
+        .unwrap_or_else(|e| panic!("Error opening material library {:?}: {}", filename, e));
+    let dir = filename.parent().unwrap_or_else(|| Path::new(""));
+    let is_mtl = filename
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mtl"));
+
+    let mut current: Option<MtlEntry> = None;
+    for line in BufReader::new(&f).lines() {
+        let l = line.unwrap();
+        let mut l_iter = l.split_whitespace();
+        let directive = l_iter.next().unwrap_or("");
+        if !is_mtl {
+            if directive == "define_material" {
+                let name = l_iter.next().unwrap().to_string();
+                let mat = define_material(l_iter, dir, materials);
+                materials.insert(name, mat);
+            }
+            continue;
+        }
+
+        if directive == "newmtl" {
+            if let Some(entry) = current.take() {
+                insert_mtl(entry, materials);
+            }
+            current = Some(MtlEntry {
+                name: l_iter.collect::<Vec<_>>().join(" "),
+                mat: mtl_default(),
+                has_amb: false,
+                tf: Color::new(0., 0., 0.),
+                illum: 2,
+            });
+            continue;
+        }
+        let entry = match current {
+            Some(ref mut entry) => entry,
+            None => continue,
+        };
+        let mat = &mut entry.mat;
+        match directive {
+            "Ka" => {
+                mat.amb = parse_color(&mut l_iter);
+                entry.has_amb = true;
+            }
+            "Kd" => mat.dif = parse_color(&mut l_iter),
+            "Ks" => mat.spec = parse_color(&mut l_iter),
+            "Ke" => mat.emission = parse_color(&mut l_iter),
+            "Tf" => entry.tf = parse_color(&mut l_iter),
+            "Ns" => mat.ns = l_iter.next().unwrap().parse().unwrap(),
+            "Ni" => mat.ior = l_iter.next().unwrap().parse().unwrap(),
+            "d" => mat.opacity = l_iter.next().unwrap().parse().unwrap(),
+            "Tr" => {
+                let transparency: f64 = l_iter.next().unwrap().parse().unwrap();
+                mat.opacity = 1. - transparency;
+            }
+            "illum" => entry.illum = l_iter.next().unwrap().parse().unwrap(),
+            "map_Ka" | "map_Kd" | "map_Ks" | "map_Ke" | "map_d" | "bump" | "map_Bump"
+            | "map_bump" | "norm" => {
+                let (file, bump_scale) = mtl_map(l_iter);
+                let channel = match directive {
+                    "map_Ka" => "amb",
+                    "map_Kd" => "dif",
+                    "map_Ks" => "spec",
+                    "map_Ke" => "emission",
+                    "map_d" => "opacity",
+                    "norm" => "normal",
+                    _ => "bump",
+                };
+                if let Some(scale) = bump_scale {
+                    mat.bump_scale = scale;
+                }
+                let texture = parse_texture(&mut Some(file.as_str()).into_iter(), channel, dir);
+                mat.textures.set(channel, texture);
+            }
+            _ => {}
+        }
+    }
+    if let Some(entry) = current {
+        insert_mtl(entry, materials);
+    }
+}
+
+struct MtlEntry {
+    name: String,
+    mat: Material,
+    has_amb: bool,
+    tf: Color,
+    illum: u32,
+}
+
+fn insert_mtl(entry: MtlEntry, materials: &mut HashMap<String, Material>) {
+    let MtlEntry {
+        name,
+        mut mat,
+        has_amb,
+        tf,
+        illum,
+    } = entry;
+    // Entries without an ambient color are lit ambiently like their diffuse
+    // color
+    if !has_amb {
+        mat.amb = mat.dif;
+        mat.textures.amb = mat.textures.dif.clone();
+    }
+    // Models 0 and 1 have no highlights and 2 only highlights, from 3 on Ks
+    // is also mirror reflection. Tf is used by the refracting models.
+    if illum < 2 {
+        mat.spec = Color::new(0., 0., 0.);
+        mat.textures.spec = None;
+    }
+    mat.mirror = illum >= 3;
+    if let 4 | 6 | 7 | 9 = illum {
+        mat.trs = tf;
+    }
+    mat.fresnel = illum == 5 || illum == 7;
+    materials.insert(name, mat);
+}
+
+fn parse_value_color(value: &str) -> Color {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() == 1 {
+        let level = parts[0].parse().unwrap();
+        return Color::new(level, level, level);
+    }
+    parse_color(&mut parts.into_iter())
+}
+
+fn mtl_default() -> Material {
+    Material {
+        amb: Color::new(0.8, 0.8, 0.8),
+        dif: Color::new(0.8, 0.8, 0.8),
+        spec: Color::new(0., 0., 0.),
+        trs: Color::new(0., 0., 0.),
+        ..Material::new()
+    }
+}
+
+fn mtl_map<'a, I: Iterator<Item = &'a str>>(args: I) -> (String, Option<f64>) {
+    let args: Vec<&str> = args.collect();
+    let file = args
+        .last()
+        .expect("MTL texture map has no file")
+        .to_string();
+    let bump_scale = args
+        .iter()
+        .position(|&a| a == "-bm")
+        .map(|i| args[i + 1].parse().unwrap());
+    (file, bump_scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scene::Sides;
+    use testing::{assert_color, TempFile};
+
+    fn define(params: &str) -> Material {
+        define_material(params.split_whitespace(), Path::new(""), &HashMap::new())
+    }
+
+    #[test]
+    fn define_material_defaults_to_matte_gray() {
+        let mat = define("");
+        assert_color(mat.dif, 0.8, 0.8, 0.8);
+        assert_color(mat.amb, 0.8, 0.8, 0.8);
+        assert_color(mat.spec, 0., 0., 0.);
+        assert_color(mat.trs, 0., 0., 0.);
+        assert_eq!(mat.opacity, 1.);
+        assert!(mat.metallic_roughness.is_none());
+        assert_eq!(mat.sides, Sides::Both);
+    }
+
+    #[test]
+    fn define_material_parses_colors_and_values() {
+        let mat = define("dif=0.2,0.4,0.6 spec=0.5 ns=32 ior=1.33 fresnel=1 sides=front");
+        assert_color(mat.dif, 0.2, 0.4, 0.6);
+        assert_color(mat.amb, 0.2, 0.4, 0.6);
+        assert_color(mat.spec, 0.5, 0.5, 0.5);
+        assert_eq!(mat.ns, 32.);
+        assert_eq!(mat.ior, 1.33);
+        assert!(mat.fresnel);
+        assert_eq!(mat.sides, Sides::Front);
+    }
+
+    #[test]
+    fn define_material_keeps_given_ambient() {
+        let mat = define("dif=0.5 amb=0.1");
+        assert_color(mat.amb, 0.1, 0.1, 0.1);
+        let mat = define("amb=0.1 dif=0.5");
+        assert_color(mat.amb, 0.1, 0.1, 0.1);
+    }
+
+    #[test]
+    fn define_material_starts_from_metallic_roughness() {
+        let mat = define("base=1,0,0 metallic=1");
+        assert!(mat.metallic_roughness.is_some());
+    }
+
+    #[test]
+    fn define_material_finds_back_material() {
+        let mut materials = HashMap::new();
+        materials.insert("inside".to_string(), define("dif=0,0,1"));
+        let mat = define_material("back=inside".split_whitespace(), Path::new(""), &materials);
+        assert_color(mat.back.unwrap().dif, 0., 0., 1.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown material parameter")]
+    fn define_material_rejects_unknown_parameters() {
+        define("shininess=3");
+    }
+
+    #[test]
+    fn mtl_ambient_falls_back_to_diffuse() {
+        let file = TempFile::with_contents(
+            "test.mtl",
+            b"# Test library\n\
+             newmtl red\n\
+             Kd 1 0 0\n\
+             \n\
+             newmtl lit glass\n\
+             Ka 0.1 0.1 0.1\n\
+             Kd 0 1 0\n\
+             Ns 50\n\
+             d 0.25\n\
+             newmtl clear\n\
+             Tr 0.75\n",
+        );
+        let mut materials = HashMap::new();
+        load(&file.path, &mut materials);
+
+        assert_eq!(materials.len(), 3);
+        let red = &materials["red"];
+        assert_color(red.amb, 1., 0., 0.);
+        assert_color(red.spec, 0., 0., 0.);
+        let lit = &materials["lit glass"];
+        assert_color(lit.amb, 0.1, 0.1, 0.1);
+        assert_color(lit.dif, 0., 1., 0.);
+        assert_eq!(lit.ns, 50.);
+        assert_eq!(lit.opacity, 0.25);
+        assert_color(lit.trs, 0., 0., 0.);
+        assert_eq!(materials["clear"].opacity, 0.25);
+    }
+
+    #[test]
+    fn mtl_illumination_model_picks_reflection_and_transmission() {
+        let file = TempFile::with_contents(
+            "illum.mtl",
+            b"newmtl matte\n\
+             Ks 0.5 0.5 0.5\n\
+             illum 1\n\
+             newmtl plastic\n\
+             Ks 0.5 0.5 0.5\n\
+             Tf 0.9 0.9 0.9\n\
+             illum 2\n\
+             newmtl glass\n\
+             Ks 0.1 0.1 0.1\n\
+             Tf 0.9 0.8 0.7\n\
+             illum 4\n\
+             newmtl fresnel glass\n\
+             Tf 1 1 1\n\
+             illum 7\n",
+        );
+        let mut materials = HashMap::new();
+        load(&file.path, &mut materials);
+
+        assert_color(materials["matte"].spec, 0., 0., 0.);
+        let plastic = &materials["plastic"];
+        assert_color(plastic.spec, 0.5, 0.5, 0.5);
+        assert!(!plastic.mirror);
+        assert_color(plastic.trs, 0., 0., 0.);
+        let glass = &materials["glass"];
+        assert!(glass.mirror);
+        assert!(!glass.fresnel);
+        assert_color(glass.trs, 0.9, 0.8, 0.7);
+        let fresnel_glass = &materials["fresnel glass"];
+        assert!(fresnel_glass.fresnel);
+        assert_color(fresnel_glass.trs, 1., 1., 1.);
+    }
+}
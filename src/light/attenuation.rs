@@ -0,0 +1,62 @@
+// Intensity falls off as 1 / (constant + linear * d + quadratic * d^2)
+#[derive(Copy, Clone, Debug)]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+    pub range: Option<f64>,
+}
+
+impl Attenuation {
+    pub fn new() -> Attenuation {
+        Attenuation {
+            constant: 0.,
+            linear: 0.,
+            quadratic: 1.,
+            range: None,
+        }
+    }
+
+    pub fn factor(&self, distance: f64) -> f64 {
+        1. / (self.constant + self.linear * distance + self.quadratic * distance.powi(2))
+    }
+
+    pub fn in_range(&self, distance: f64) -> bool {
+        self.range.is_none_or(|range| distance <= range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::assert_close;
+
+    #[test]
+    fn falls_off_with_the_inverse_square_by_default() {
+        let attenuation = Attenuation::new();
+        assert_close(attenuation.factor(1.), 1.);
+        assert_close(attenuation.factor(2.), 0.25);
+        assert_close(attenuation.factor(10.), 0.01);
+    }
+
+    #[test]
+    fn combines_the_coefficients() {
+        let attenuation = Attenuation {
+            constant: 1.,
+            linear: 0.5,
+            quadratic: 0.25,
+            range: None,
+        };
+        assert_close(attenuation.factor(0.), 1.);
+        assert_close(attenuation.factor(2.), 1. / 3.);
+    }
+
+    #[test]
+    fn limits_range_only_when_given() {
+        let mut attenuation = Attenuation::new();
+        assert!(attenuation.in_range(1e9));
+        attenuation.range = Some(5.);
+        assert!(attenuation.in_range(5.));
+        assert!(!attenuation.in_range(5.01));
+    }
+}
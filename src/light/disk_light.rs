@@ -0,0 +1,54 @@
+use std::f64::consts::PI;
+
+use math::{Ray, Vector, TMIN};
+use math::sampling::{disk_offset, stratified_samples};
+use color::Color;
+use light::{area_sample, Attenuation, Light, LightSample};
+
+#[derive(Copy, Clone, Debug)]
+pub struct DiskLight {
+    pub pos: Vector,
+    pub normal: Vector,
+    pub r: f64,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    pub sample_count: u32,
+    pub visible: bool,
+}
+
+impl Light for DiskLight {
+    fn position(&self) -> Vector {
+        self.pos
+    }
+
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample> {
+        let normal = self.normal.normalized();
+        let (u, v) = normal.perpendiculars();
+        stratified_samples(self.sample_count)
+            .into_iter()
+            .filter_map(|ab| {
+                let pos = self.pos + disk_offset(ab, self.r, u, v);
+                area_sample(
+                    point_hit,
+                    pos,
+                    normal,
+                    self.intensity,
+                    PI * self.r.powi(2),
+                    &self.attenuation,
+                    self.sample_count as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn emitter_hit(&self, ray: Ray) -> Option<(f64, Color)> {
+        if !self.visible {
+            return None;
+        }
+        let t = (self.pos - ray.pos).dot(&self.normal) / ray.dir.dot(&self.normal);
+        if !t.is_finite() || t < TMIN || (ray.find_point(t) - self.pos).magnitude() > self.r {
+            return None;
+        }
+        Some((t, self.intensity))
+    }
+}
@@ -0,0 +1,36 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use math::Vector;
+use math::sampling::stratified_samples;
+use environment::{Environment, EnvironmentMap};
+use light::{Light, LightSample};
+
+#[derive(Clone)]
+pub struct EnvironmentLight {
+    pub map: Arc<EnvironmentMap>,
+    pub sample_count: u32,
+}
+
+impl Light for EnvironmentLight {
+    fn distance(&self, _point_hit: Vector) -> f64 {
+        f64::INFINITY
+    }
+
+    fn samples(&self, _point_hit: Vector) -> Vec<LightSample> {
+        let n = self.sample_count as f64;
+        stratified_samples(self.sample_count)
+            .into_iter()
+            .filter_map(|ab| {
+                let (l, pdf) = self.map.sample(ab)?;
+                // Dividing by pi makes a uniform map light surfaces as
+                // brightly as a directional light of the same color.
+                Some(LightSample {
+                    l,
+                    distance: f64::INFINITY,
+                    intensity: self.map.radiance(l) / (PI * pdf * n),
+                })
+            })
+            .collect()
+    }
+}
@@ -1,52 +1,79 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 
-use intersect::Intersectable;
-use math::Vector;
+use math::{Ray, Vector};
 use color::Color;
 
+pub use self::attenuation::Attenuation;
 pub use self::point_light::PointLight;
 pub use self::directional_light::DirectionalLight;
+pub use self::rectangle_light::RectangleLight;
+pub use self::disk_light::DiskLight;
+pub use self::sphere_light::SphereLight;
+pub use self::spot_light::SpotLight;
+pub use self::environment_light::EnvironmentLight;
+pub use self::triangle_light::TriangleLight;
 
+mod attenuation;
 mod point_light;
 mod directional_light;
+mod rectangle_light;
+mod disk_light;
+mod sphere_light;
+mod spot_light;
+mod environment_light;
+mod triangle_light;
+
+#[derive(Copy, Clone, Debug)]
+pub struct LightSample {
+    pub l: Vector,
+    pub distance: f64,
+    pub intensity: Color,
+}
 
 pub trait Light {
     fn position(&self) -> Vector {
         Vector::new()
     }
 
-    fn v(&self, point_hit: Vector, camera_pos: Vector) -> Vector {
-        (camera_pos - point_hit).normalized()
-    }
-
     fn l(&self, point_hit: Vector) -> Vector {
         (self.position() - point_hit).normalized()
     }
 
-    fn n(&self, point_hit: Vector, v: Vector, shape_hit: &Intersectable) -> Vector {
-        shape_hit.surface_normal(point_hit, v)
+    fn distance(&self, point_hit: Vector) -> f64 {
+        (self.position() - point_hit).magnitude()
     }
 
-    fn r(&self, point_hit: Vector, v: Vector, shape_hit: &Intersectable) -> Vector {
-        let l = self.l(point_hit);
-        let n = self.n(point_hit, v, shape_hit);
-        l.reflect(&n).normalized()
-    }
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample>;
 
-    fn compute_diffuse_component(
-        &self,
-        point_hit: Vector,
-        v: Vector,
-        shape_hit: &Intersectable,
-    ) -> Color;
-
-    fn compute_specular_component(
-        &self,
-        point_hit: Vector,
-        v: Vector,
-        shape_hit: &Intersectable,
-        camera_pos: Vector,
-    ) -> Color;
+    fn emitter_hit(&self, _ray: Ray) -> Option<(f64, Color)> {
+        None
+    }
 }
 
-pub type Lightable = Arc<Light>;
\ No newline at end of file
+pub type Lightable = Arc<dyn Light>;
+
+// Point light standing in for a 1/n share of the surface, with
+// radiance * area / PI to match the diffuse terms
+fn area_sample(
+    point_hit: Vector,
+    pos: Vector,
+    normal: Vector,
+    radiance: Color,
+    area: f64,
+    attenuation: &Attenuation,
+    n: usize,
+) -> Option<LightSample> {
+    let to_light = pos - point_hit;
+    let distance = to_light.magnitude();
+    if !attenuation.in_range(distance) {
+        return None;
+    }
+    let l = to_light / distance;
+    let cos_light = normal.dot(&l).abs();
+    Some(LightSample {
+        l,
+        distance,
+        intensity: radiance * (area / PI * cos_light * attenuation.factor(distance) / n as f64),
+    })
+}
@@ -0,0 +1,65 @@
+use math::{Ray, Vector, TMIN};
+use math::sampling::stratified_samples;
+use color::Color;
+use light::{area_sample, Attenuation, Light, LightSample};
+
+#[derive(Copy, Clone, Debug)]
+pub struct RectangleLight {
+    pub pos: Vector,
+    pub u: Vector,
+    pub v: Vector,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    pub sample_count: u32,
+    pub visible: bool,
+}
+
+impl RectangleLight {
+    fn normal(&self) -> Vector {
+        self.u.cross(&self.v).normalized()
+    }
+}
+
+impl Light for RectangleLight {
+    fn position(&self) -> Vector {
+        self.pos + (self.u + self.v) / 2.
+    }
+
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample> {
+        let normal = self.normal();
+        stratified_samples(self.sample_count)
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let pos = self.pos + self.u * a + self.v * b;
+                area_sample(
+                    point_hit,
+                    pos,
+                    normal,
+                    self.intensity,
+                    self.u.cross(&self.v).magnitude(),
+                    &self.attenuation,
+                    self.sample_count as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn emitter_hit(&self, ray: Ray) -> Option<(f64, Color)> {
+        if !self.visible {
+            return None;
+        }
+        let normal = self.normal();
+        let t = (self.pos - ray.pos).dot(&normal) / ray.dir.dot(&normal);
+        if !t.is_finite() || t < TMIN {
+            return None;
+        }
+        let d = ray.find_point(t) - self.pos;
+        let a = d.dot(&self.u) / self.u.dot(&self.u);
+        let b = d.dot(&self.v) / self.v.dot(&self.v);
+        if (0. ..=1.).contains(&a) && (0. ..=1.).contains(&b) {
+            Some((t, self.intensity))
+        } else {
+            None
+        }
+    }
+}
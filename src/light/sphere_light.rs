@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use math::{Ray, Vector, TMIN};
+use math::sampling::{disk_offset, stratified_samples};
+use color::Color;
+use light::{area_sample, Attenuation, Light, LightSample};
+
+// Samples are taken across the disk facing the shaded point
+#[derive(Copy, Clone, Debug)]
+pub struct SphereLight {
+    pub pos: Vector,
+    pub r: f64,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    pub sample_count: u32,
+    pub visible: bool,
+}
+
+impl Light for SphereLight {
+    fn position(&self) -> Vector {
+        self.pos
+    }
+
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample> {
+        let normal = (point_hit - self.pos).normalized();
+        let (u, v) = normal.perpendiculars();
+        stratified_samples(self.sample_count)
+            .into_iter()
+            .filter_map(|ab| {
+                let pos = self.pos + disk_offset(ab, self.r, u, v);
+                area_sample(
+                    point_hit,
+                    pos,
+                    normal,
+                    self.intensity,
+                    PI * self.r.powi(2),
+                    &self.attenuation,
+                    self.sample_count as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn emitter_hit(&self, ray: Ray) -> Option<(f64, Color)> {
+        if !self.visible {
+            return None;
+        }
+        let c_to_p = ray.pos - self.pos;
+        let a = ray.dir.dot(&ray.dir);
+        let b = ray.dir.dot(&c_to_p);
+        let disc = b.powi(2) - a * (c_to_p.dot(&c_to_p) - self.r.powi(2));
+        if disc < 0. {
+            return None;
+        }
+        let t_minus = (-b - disc.sqrt()) / a;
+        let t_plus = (-b + disc.sqrt()) / a;
+        let t = if t_minus > TMIN { t_minus } else { t_plus };
+        if t > TMIN {
+            Some((t, self.intensity))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use math::Vector;
+use color::Color;
+use light::{Attenuation, Light, LightSample};
+use texture::ImageTexture;
+
+#[derive(Clone, Debug)]
+pub struct SpotLight {
+    pub pos: Vector,
+    pub dir: Vector,
+    pub inner: f64,
+    pub outer: f64,
+    pub falloff: f64,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    pub cookie: Option<Arc<ImageTexture>>,
+}
+
+impl SpotLight {
+    fn cone_factor(&self, d: Vector) -> Color {
+        let axis = self.dir.normalized();
+        let cos_theta = d.dot(&axis);
+        let cos_outer = self.outer.cos();
+        let cos_inner = self.inner.cos();
+        if cos_theta <= cos_outer {
+            return Color::new(0., 0., 0.);
+        }
+
+        let factor = if cos_theta >= cos_inner {
+            1.
+        } else {
+            ((cos_theta - cos_outer) / (cos_inner - cos_outer)).powf(self.falloff)
+        };
+
+        match self.cookie {
+            Some(ref cookie) => {
+                // Project the direction onto a plane across the cone so the
+                // cookie exactly covers the outer angle.
+                let (u, v) = axis.perpendiculars();
+                let extent = self.outer.tan() * cos_theta;
+                let s = 0.5 + 0.5 * d.dot(&u) / extent;
+                let t = 0.5 + 0.5 * d.dot(&v) / extent;
+                cookie.sample(s, t) * factor
+            }
+            None => Color::new(1., 1., 1.) * factor,
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn position(&self) -> Vector {
+        self.pos
+    }
+
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample> {
+        let distance = self.distance(point_hit);
+        if !self.attenuation.in_range(distance) {
+            return Vec::new();
+        }
+        let l = self.l(point_hit);
+        let cone = self.cone_factor(l * -1.);
+        if !cone.is_not_black() {
+            return Vec::new();
+        }
+        vec![LightSample {
+            l,
+            distance,
+            intensity: self.intensity * cone * self.attenuation.factor(distance),
+        }]
+    }
+}
@@ -0,0 +1,57 @@
+use math::{Vector, TMIN};
+use math::sampling::stratified_samples;
+use color::Color;
+use light::{area_sample, Attenuation, Light, LightSample};
+
+// The triangle itself shows up in renders, so this can't be hit directly
+#[derive(Copy, Clone, Debug)]
+pub struct TriangleLight {
+    pub v1: Vector,
+    pub v2: Vector,
+    pub v3: Vector,
+    pub emission: Color,
+    pub attenuation: Attenuation,
+    pub sample_count: u32,
+}
+
+impl TriangleLight {
+    fn normal(&self) -> Vector {
+        (self.v2 - self.v1).cross(&(self.v3 - self.v1)).normalized()
+    }
+
+    fn area(&self) -> f64 {
+        (self.v2 - self.v1).cross(&(self.v3 - self.v1)).magnitude() / 2.
+    }
+}
+
+impl Light for TriangleLight {
+    fn position(&self) -> Vector {
+        (self.v1 + self.v2 + self.v3) / 3.
+    }
+
+    fn samples(&self, point_hit: Vector) -> Vec<LightSample> {
+        let normal = self.normal();
+        let area = self.area();
+        stratified_samples(self.sample_count)
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let s = a.sqrt();
+                let pos = self.v1 * (1. - s) + self.v2 * (s * (1. - b)) + self.v3 * (s * b);
+                area_sample(
+                    point_hit,
+                    pos,
+                    normal,
+                    self.emission,
+                    area,
+                    &self.attenuation,
+                    self.sample_count as usize,
+                )
+            })
+            .map(|mut sample| {
+                // Keep the triangle from shadowing itself
+                sample.distance -= TMIN;
+                sample
+            })
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 extern crate raytracer;
 
-use raytracer::run;
+use raytracer::{run, run_with_integrator};
 use std::env;
 
 fn main() {
@@ -10,5 +10,9 @@ fn main() {
         scene_file = &args[1];
     }
 
-    run(scene_file);
+    if args.len() > 2 {
+        run_with_integrator(scene_file, &args[2]);
+    } else {
+        run(scene_file);
+    }
 }
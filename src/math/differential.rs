@@ -0,0 +1,63 @@
+use math::{Ray, Vector};
+
+#[derive(Copy, Clone, Debug)]
+pub struct RayDifferential {
+    pub rx: Ray,
+    pub ry: Ray,
+}
+
+impl RayDifferential {
+    pub fn footprint(&self, point: Vector, n: Vector) -> Option<(Vector, Vector)> {
+        let offset = |r: &Ray| {
+            let t = n.dot(&(point - r.pos)) / n.dot(&r.dir);
+            if t.is_finite() {
+                Some(r.find_point(t) - point)
+            } else {
+                None
+            }
+        };
+        match (offset(&self.rx), offset(&self.ry)) {
+            (Some(dpdx), Some(dpdy)) => Some((dpdx, dpdy)),
+            _ => None,
+        }
+    }
+
+    pub fn reflect(
+        &self,
+        point: Vector,
+        (dpdx, dpdy): (Vector, Vector),
+        n: Vector,
+    ) -> RayDifferential {
+        RayDifferential {
+            rx: Ray {
+                pos: point + dpdx,
+                dir: (self.rx.dir * -1.).reflect(&n),
+            },
+            ry: Ray {
+                pos: point + dpdy,
+                dir: (self.ry.dir * -1.).reflect(&n),
+            },
+        }
+    }
+
+    pub fn refract(
+        &self,
+        point: Vector,
+        (dpdx, dpdy): (Vector, Vector),
+        n: Vector,
+        eta: f64,
+    ) -> Option<RayDifferential> {
+        let rx = self.rx.dir.normalized().refract(&n, eta)?;
+        let ry = self.ry.dir.normalized().refract(&n, eta)?;
+        Some(RayDifferential {
+            rx: Ray {
+                pos: point + dpdx,
+                dir: rx,
+            },
+            ry: Ray {
+                pos: point + dpdy,
+                dir: ry,
+            },
+        })
+    }
+}
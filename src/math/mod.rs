@@ -1,7 +1,10 @@
 mod matrix;
 mod vector;
 mod ray;
+mod differential;
+pub mod sampling;
 
 pub use self::matrix::Matrix;
 pub use self::ray::{Ray, TMAX, TMIN};
+pub use self::differential::RayDifferential;
 pub use self::vector::Vector;
@@ -0,0 +1,76 @@
+extern crate rand;
+
+use std::f64::consts::PI;
+
+use math::Vector;
+
+use self::rand::random;
+
+pub fn uniform() -> f64 {
+    random::<f64>()
+}
+
+// One jittered sample in each of `n` cells of equal area
+pub fn stratified_samples(n: u32) -> Vec<(f64, f64)> {
+    let rows = ((n as f64).sqrt() as u32).max(1);
+    let mut samples = Vec::with_capacity(n as usize);
+    let mut y = 0.;
+    for row in 0..rows {
+        // The first rows take one more of the samples left over
+        let cols = n / rows + u32::from(row < n % rows);
+        let height = cols as f64 / n as f64;
+        for col in 0..cols {
+            samples.push((
+                (col as f64 + uniform()) / cols as f64,
+                y + uniform() * height,
+            ));
+        }
+        y += height;
+    }
+    samples
+}
+
+pub fn disk_offset((a, b): (f64, f64), r: f64, u: Vector, v: Vector) -> Vector {
+    let radius = a.sqrt() * r;
+    let theta = 2. * PI * b;
+    u * (radius * theta.cos()) + v * (radius * theta.sin())
+}
+
+pub fn cosine_hemisphere((a, b): (f64, f64), n: Vector) -> Vector {
+    let (u, v) = n.perpendiculars();
+    disk_offset((a, b), 1., u, v) + n * (1. - a).max(0.).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stratified_samples_fill_the_unit_square() {
+        for n in 1..20 {
+            let samples = stratified_samples(n);
+            assert_eq!(samples.len(), n as usize);
+            for &(a, b) in &samples {
+                assert!((0. ..1.).contains(&a) && (0. ..1.).contains(&b));
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_samples_put_one_sample_in_each_cell() {
+        // 5 samples make a row of 3 over a row of 2, with heights in
+        // proportion to their counts
+        let cells = [
+            (0., 1. / 3., 0., 0.6),
+            (1. / 3., 2. / 3., 0., 0.6),
+            (2. / 3., 1., 0., 0.6),
+            (0., 0.5, 0.6, 1.),
+            (0.5, 1., 0.6, 1.),
+        ];
+        for _ in 0..100 {
+            for (&(a, b), &(a0, a1, b0, b1)) in stratified_samples(5).iter().zip(&cells) {
+                assert!(a0 <= a && a < a1 && b0 <= b && b < b1, "({}, {})", a, b);
+            }
+        }
+    }
+}
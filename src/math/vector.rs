@@ -48,6 +48,18 @@ impl Vector {
         Vector { x, y, z }
     }
 
+    pub fn perpendiculars(&self) -> (Vector, Vector) {
+        let w = self.normalized();
+        let a = if w.x.abs() > 0.9 {
+            Vector { x: 0., y: 1., z: 0. }
+        } else {
+            Vector { x: 1., y: 0., z: 0. }
+        };
+        let u = w.cross(&a).normalized();
+        let v = w.cross(&u);
+        (u, v)
+    }
+
     // pub fn angle(&self, other: &Vector) -> f64 {
     //     (self.dot(other) / (self.magnitude() * other.magnitude())).acos()
     // }
@@ -55,6 +67,16 @@ impl Vector {
     pub fn reflect(&self, other: &Vector) -> Vector {
         *other * 2. * self.dot(other) - *self
     }
+
+    // `eta` is the incident over the transmitted index of refraction
+    pub fn refract(&self, n: &Vector, eta: f64) -> Option<Vector> {
+        let cos_i = -self.dot(n);
+        let k = 1. - eta.powi(2) * (1. - cos_i.powi(2));
+        if k < 0. {
+            return None;
+        }
+        Some(*self * eta + *n * (eta * cos_i - k.sqrt()))
+    }
 }
 
 impl ops::Add for Vector {
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+
+use intersect::Triangle;
+use math::Vector;
+use scene::Material;
+use library;
+
+pub fn load(
+    filename: &Path,
+    default_material: &Material,
+    materials: &mut HashMap<String, Material>,
+) -> Vec<Triangle> {
+    let f = File::open(filename)
+        .unwrap_or_else(|e| panic!("Error opening OBJ file {:?}: {}", filename, e));
+    let dir = filename.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut vertices: Vec<Vector> = Vec::new();
+    let mut texcoords: Vec<(f64, f64)> = Vec::new();
+    let mut material = default_material.clone();
+    let mut triangles = Vec::new();
+    for line in BufReader::new(&f).lines() {
+        let l = line.unwrap();
+        let mut l_iter = l.split_whitespace();
+        match l_iter.next().unwrap_or("") {
+            "v" => {
+                let x = l_iter.next().unwrap().parse().unwrap();
+                let y = l_iter.next().unwrap().parse().unwrap();
+                let z = l_iter.next().unwrap().parse().unwrap();
+                vertices.push(Vector { x, y, z });
+            }
+            "vt" => {
+                let u = l_iter.next().unwrap().parse().unwrap();
+                let v = l_iter.next().map_or(0., |v| v.parse().unwrap());
+                texcoords.push((u, v));
+            }
+            "f" => {
+                let corners: Vec<(usize, Option<usize>)> = l_iter
+                    .map(|corner| {
+                        let mut indexes = corner.split('/');
+                        let v = index(indexes.next().unwrap(), vertices.len());
+                        let t = indexes
+                            .next()
+                            .filter(|t| !t.is_empty())
+                            .map(|t| index(t, texcoords.len()));
+                        (v, t)
+                    })
+                    .collect();
+                for i in 1..corners.len().saturating_sub(1) {
+                    let (a, b, c) = (corners[0], corners[i], corners[i + 1]);
+                    let uvs = match (a.1, b.1, c.1) {
+                        (Some(ta), Some(tb), Some(tc)) => {
+                            Some([texcoords[ta], texcoords[tb], texcoords[tc]])
+                        }
+                        _ => None,
+                    };
+                    triangles.push(Triangle {
+                        v1: vertices[a.0],
+                        v2: vertices[b.0],
+                        v3: vertices[c.0],
+                        uvs,
+                        mat: material.clone(),
+                    });
+                }
+            }
+            "mtllib" => {
+                for file in l_iter {
+                    library::load(&dir.join(file), materials);
+                }
+            }
+            "usemtl" => {
+                let name = l_iter.collect::<Vec<_>>().join(" ");
+                material = materials
+                    .get(&name)
+                    .unwrap_or_else(|| panic!("Unknown material {}", name))
+                    .clone();
+            }
+            _ => continue,
+        }
+    }
+    triangles
+}
+
+// 1-based, or negative counting back from the end
+fn index(i: &str, count: usize) -> usize {
+    let i: i64 = i.parse().unwrap();
+    if i < 0 {
+        (count as i64 + i) as usize
+    } else {
+        (i - 1) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::{assert_vector, TempFile};
+
+    fn load_str(name: &str, contents: &str) -> Vec<Triangle> {
+        let file = TempFile::with_contents(name, contents.as_bytes());
+        load(&file.path, &Material::new(), &mut HashMap::new())
+    }
+
+    #[test]
+    fn splits_polygons_into_fans() {
+        let triangles = load_str(
+            "fan.obj",
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 2 1\nf 1 2 3 4 5\n",
+        );
+        assert_eq!(triangles.len(), 3);
+        let corners = [(1., 0., 0.), (1., 1., 0.), (0., 1., 0.), (0., 2., 1.)];
+        for (i, t) in triangles.iter().enumerate() {
+            let (b, c) = (corners[i], corners[i + 1]);
+            assert_vector(t.v1, 0., 0., 0.);
+            assert_vector(t.v2, b.0, b.1, b.2);
+            assert_vector(t.v3, c.0, c.1, c.2);
+        }
+    }
+
+    #[test]
+    fn reads_negative_indices_relative_to_the_end() {
+        let triangles = load_str(
+            "negative.obj",
+            "v 9 9 9\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n",
+        );
+        assert_eq!(triangles.len(), 1);
+        assert_vector(triangles[0].v1, 0., 0., 0.);
+        assert_vector(triangles[0].v2, 1., 0., 0.);
+        assert_vector(triangles[0].v3, 0., 1., 0.);
+    }
+
+    #[test]
+    fn skips_missing_texture_coordinates() {
+        let triangles = load_str(
+            "normals.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n",
+        );
+        assert_eq!(triangles.len(), 1);
+        assert!(triangles[0].uvs.is_none());
+        assert_vector(triangles[0].v2, 1., 0., 0.);
+    }
+
+    #[test]
+    fn reads_texture_coordinates() {
+        let triangles = load_str(
+            "uvs.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nf 1/1/1 2/2/1 3/-1/1\n",
+        );
+        assert_eq!(triangles[0].uvs, Some([(0., 0.), (1., 0.), (0., 1.)]));
+    }
+}
@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use color::Color;
+
+pub fn read(filename: &str) -> Result<(usize, usize, Vec<Color>)> {
+    let mut bytes = Vec::new();
+    File::open(filename)?.read_to_end(&mut bytes)?;
+
+    // The header is four whitespace separated tokens, with exactly one
+    // whitespace character between the last one and the pixel data.
+    let mut header = Vec::new();
+    let mut pos = 0;
+    while header.len() < 4 {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(invalid("truncated PFM header"));
+        }
+        header.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+    }
+    pos += 1;
+
+    let channels = match header[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("not a PFM file")),
+    };
+    let width: usize = header[1].parse().map_err(|_| invalid("bad PFM width"))?;
+    let height: usize = header[2].parse().map_err(|_| invalid("bad PFM height"))?;
+    let scale: f64 = header[3].parse().map_err(|_| invalid("bad PFM scale"))?;
+    let little_endian = scale < 0.;
+
+    let data = &bytes[pos.min(bytes.len())..];
+    if data.len() < width * height * channels * 4 {
+        return Err(invalid("truncated PFM data"));
+    }
+    let value = |i: usize| {
+        let b = [data[i * 4], data[i * 4 + 1], data[i * 4 + 2], data[i * 4 + 3]];
+        let v = if little_endian {
+            f32::from_le_bytes(b)
+        } else {
+            f32::from_be_bytes(b)
+        };
+        v as f64
+    };
+
+    // Rows are stored from the bottom of the image up
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let i = (y * width + x) * channels;
+            pixels.push(if channels == 3 {
+                Color::new(value(i), value(i + 1), value(i + 2))
+            } else {
+                Color::new(value(i), value(i), value(i))
+            });
+        }
+    }
+    Ok((width, height, pixels))
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+pub fn write(filename: &str, width: usize, height: usize, pixels: &[Color]) -> Result<()> {
+    let mut bytes = format!("PF\n{} {}\n-1.0\n", width, height).into_bytes();
+    bytes.reserve(width * height * 12);
+    // Rows are stored from the bottom of the image up
+    for y in (0..height).rev() {
+        for c in &pixels[y * width..(y + 1) * width] {
+            for v in &[c.r, c.g, c.b] {
+                bytes.extend_from_slice(&(*v as f32).to_le_bytes());
+            }
+        }
+    }
+    File::create(filename)?.write_all(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use testing::{assert_color, TempFile};
+
+    #[test]
+    fn round_trip() {
+        let file = TempFile::new("round_trip.pfm");
+        let pixels: Vec<Color> = (0..6)
+            .map(|i| Color::new(i as f64, i as f64 + 0.5, -(i as f64) * 0.25))
+            .collect();
+        write(file.name(), 3, 2, &pixels).unwrap();
+        let (width, height, read_pixels) = read(file.name()).unwrap();
+
+        assert_eq!((width, height), (3, 2));
+        for (read, written) in read_pixels.iter().zip(&pixels) {
+            assert_color(*read, written.r, written.g, written.b);
+        }
+    }
+
+    #[test]
+    fn writes_bottom_row_first_in_little_endian() {
+        let file = TempFile::new("layout.pfm");
+        let pixels = vec![Color::new(1., 2., 3.), Color::new(4., 5., 6.)];
+        write(file.name(), 1, 2, &pixels).unwrap();
+        let bytes = fs::read(&file.path).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], &header[..]);
+        let data = &bytes[header.len()..];
+        assert_eq!(data.len(), 24);
+        assert_eq!(&data[..4], &4f32.to_le_bytes());
+        assert_eq!(&data[12..16], &1f32.to_le_bytes());
+    }
+
+    #[test]
+    fn reads_big_endian_grayscale() {
+        let mut bytes = b"Pf\n2 1\n1.0\n".to_vec();
+        bytes.extend_from_slice(&0.5f32.to_be_bytes());
+        bytes.extend_from_slice(&2f32.to_be_bytes());
+        let file = TempFile::with_contents("big_endian.pfm", &bytes);
+        let (width, height, pixels) = read(file.name()).unwrap();
+
+        assert_eq!((width, height), (2, 1));
+        assert_color(pixels[0], 0.5, 0.5, 0.5);
+        assert_color(pixels[1], 2., 2., 2.);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let file = TempFile::with_contents("truncated.pfm", b"PF\n2 2\n-1.0\n\0\0\0\0");
+        assert_eq!(
+            read(file.name()).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
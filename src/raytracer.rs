@@ -1,52 +1,99 @@
-extern crate image;
-
-use std::path::Path;
 // use std::thread;
 
 use color::Color;
+use framebuffer::Framebuffer;
 use scene::Scene;
-use math::Ray;
-use intersect::{Hit, Intersect};
+use math::{Ray, RayDifferential};
+use math::sampling::stratified_samples;
+use integrator::{self, Integrator};
 
 pub fn run(scene_file: &String) {
-    let rt = Raytracer::new(scene_file);
+    let rt = Raytracer::new(scene_file, None);
+    rt.run()
+}
+
+pub fn run_with_integrator(scene_file: &String, integrator: &str) {
+    let rt = Raytracer::new(scene_file, Some(integrator));
     rt.run()
 }
 
 struct Raytracer {
     scene: Scene,
+    integrator: Box<dyn Integrator>,
 }
 
 impl Raytracer {
-    fn new(scene_file: &String) -> Raytracer {
-        Raytracer {
-            scene: Scene::new(scene_file),
-        }
+    fn new(scene_file: &String, integrator: Option<&str>) -> Raytracer {
+        let scene = Scene::new(scene_file);
+        let integrator = {
+            let name = integrator.unwrap_or(&scene.integrator);
+            match integrator::by_name(name) {
+                Some(integrator) => integrator,
+                None => panic!("Unknown integrator {}", name),
+            }
+        };
+        Raytracer { scene, integrator }
     }
 
     fn run(&self) {
-        let mut i = image::RgbImage::new(self.scene.width, self.scene.height);
-        // let mut colors: [Color; ]
-
-        for (x, y, pixel) in i.enumerate_pixels_mut() {
-            // println!("Image x, y = {}, {}", x, y);
-            // thread::spawn(move || {
-            *pixel = image::Rgb(self.get_color_for_pixel(x as f64, y as f64).to_u8_array());
-            // });
+        let (width, height) = (self.scene.width, self.scene.height);
+        let mut framebuffer = if self.scene.alpha_output {
+            Framebuffer::with_alpha(width, height, self.scene.transparent_background)
+        } else {
+            Framebuffer::new(width, height)
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                // thread::spawn(move || {
+                let (color, alpha) = self.get_color_for_pixel(x as f64, y as f64);
+                framebuffer.set(x, y, color, alpha);
+                // });
+            }
+        }
+
+        framebuffer.save(&self.scene.filename, &self.scene.output_transform);
+    }
+
+    fn get_color_for_pixel(&self, x: f64, y: f64) -> (Color, f64) {
+        let n = self.scene.samples_per_pixel;
+        if n <= 1 {
+            return self.get_radiance(x + 0.5, y + 0.5, 1.);
         }
 
-        i.save(Path::new(&self.scene.filename))
-            .expect("Error saving image");
+        // Samples are spread over a grid, so each covers a cell this wide
+        let spacing = 1. / (n as f64).sqrt();
+        let mut color = Color::new(0., 0., 0.);
+        let mut alpha = 0.;
+        for (dx, dy) in stratified_samples(n) {
+            let (c, a) = self.get_radiance(x + dx, y + dy, spacing);
+            color += c;
+            alpha += a;
+        }
+        (color / n as f64, alpha / n as f64)
     }
 
-    fn get_color_for_pixel(&self, x: f64, y: f64) -> Color {
-        let r = self.get_ray_through_canvas(x, y);
-        self.evaluate_ray_tree(r, 0)
+    fn get_radiance(&self, x: f64, y: f64, spacing: f64) -> (Color, f64) {
+        let ray = self.get_ray_through_canvas(x, y);
+        let differential = RayDifferential {
+            rx: self.get_ray_through_canvas(x + spacing, y),
+            ry: self.get_ray_through_canvas(x, y + spacing),
+        };
+        let (color, covered) = self
+            .integrator
+            .radiance(&self.scene, ray, Some(differential));
+        if covered {
+            (color, 1.)
+        } else if self.scene.transparent_background {
+            (Color::new(0., 0., 0.), 0.)
+        } else {
+            (color, 0.)
+        }
     }
 
     fn get_ray_through_canvas(&self, x: f64, y: f64) -> Ray {
-        let u = self.scene.canvas.left + x + 0.5;
-        let v = self.scene.canvas.bottom + y + 0.5;
+        let u = self.scene.canvas.left + x;
+        let v = self.scene.canvas.bottom + y;
 
         let mut dir = self.scene.camera.right * u + self.scene.camera.up * v
             - self.scene.camera.dir * self.scene.canvas.depth;
@@ -61,90 +108,4 @@ impl Raytracer {
 
         ray
     }
-
-    fn evaluate_ray_tree(&self, ray: Ray, current_depth: u32) -> Color {
-        let current_depth = current_depth + 1;
-        let hit = self.get_ray_intersection(ray);
-        if hit.hit && current_depth <= self.scene.max_depth + 1 {
-            // println!("Ray hit something");
-            self.apply_lighting_model(hit, current_depth)
-        } else {
-            // println!("Ray missed");
-            self.scene.background
-        }
-    }
-
-    fn apply_lighting_model(&self, hit: Hit, current_depth: u32) -> Color {
-        let mut color = Color::new(0., 0., 0.);
-        let shape = hit.shape.unwrap();
-        let mat = shape.get_material();
-
-        color += shape.get_material().amb * self.scene.ambient_light;
-
-        let point_hit = hit.ray.find_point(hit.t);
-        let v = (hit.ray.pos - point_hit).normalized();
-        let mut n = shape.surface_normal(point_hit, v);
-
-        for light in self.scene.lights.iter() {
-            let l = light.l(point_hit);
-            let diff = light.compute_diffuse_component(point_hit, v, &shape);
-            // println!("Diffuse component is {:?}", diff);
-            let spec =
-                light.compute_specular_component(point_hit, v, &shape, self.scene.camera.pos);
-
-            // Check shadow
-            let shadow = Ray {
-                pos: point_hit,
-                dir: l,
-            };
-            let shadow_hit = self.get_ray_intersection(shadow);
-            if shadow_hit.hit {
-                let p = shadow_hit.ray.find_point(shadow_hit.t);
-                if (p - point_hit).magnitude() < (light.position() - point_hit).magnitude() {
-                    // printf("Shadowing\n");
-                    continue;
-                }
-            }
-
-            color += diff + spec;
-        }
-
-        if mat.spec.is_not_black() {
-            let reflect = Ray {
-                pos: point_hit,
-                dir: v.reflect(&n),
-            };
-            color += mat.spec * self.evaluate_ray_tree(reflect, current_depth);
-        }
-
-        if mat.trs.is_not_black() {
-            let d = v * -1.;
-            let ior_i: f64;
-            let ior_r: f64;
-            if v.dot(&n) > 0. {
-                // Going into solid
-                ior_i = 1.;
-                ior_r = mat.ior;
-            } else {
-                // Going out of solid
-                ior_i = mat.ior;
-                ior_r = 1.;
-                n = n * -1.;
-            }
-
-            let refract_v = ((d - n * d.dot(&n)) * ior_i / ior_r)
-                - n * (1. - (ior_i.powi(2) * (1. - d.dot(&n).powi(2))) / ior_r.powi(2)).sqrt();
-            let refract = Ray {
-                pos: point_hit,
-                dir: refract_v,
-            };
-            color += mat.trs * self.evaluate_ray_tree(refract, current_depth);
-        }
-
-        color
-    }
-
-    fn get_ray_intersection(&self, ray: Ray) -> Hit {
-        self.scene.bvhroot.get_ray_intersection(ray)
-    }
 }
@@ -1,14 +1,24 @@
+use std::collections::HashMap;
 use std::f64::consts::PI;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
+use std::path::Path;
 use std::sync::Arc;
 
 use intersect::{Sphere, Triangle, Intersectable};
 use color::Color;
 use math::Vector;
-use light::{DirectionalLight, PointLight, Lightable};
+use light::{Attenuation, DirectionalLight, DiskLight, EnvironmentLight, Lightable, PointLight,
+            RectangleLight, SphereLight, SpotLight, TriangleLight};
+use environment::{Backplate, EnvironmentMap, Environmental, Gradient, Sky};
 use bvh::{Axis, BVHNode};
+use integrator::AmbientOcclusion;
+use brdf::{DiffuseModel, MetallicRoughness, SpecularModel};
+use texture::{ImageTexture, Pattern, Procedural, Space, TexturePoint, Texturable, Wrap};
+use library;
+use obj;
+use tonemap::{OutputTransform, ToneMap};
 
 
 pub struct Scene {
@@ -16,12 +26,22 @@ pub struct Scene {
     pub height: u32,
     pub filename: String,
     pub background: Color,
+    pub environment: Option<Environmental>,
+    pub camera_environment: Option<Environmental>,
+    pub backplate: Option<Backplate>,
     pub ambient_light: Color,
+    pub ambient_occlusion: Option<AmbientOcclusion>,
     pub camera: Camera,
     pub canvas: Canvas,
     pub shapes: Vec<Intersectable>,
     pub lights: Vec<Lightable>,
     pub max_depth: u32,
+    pub samples_per_pixel: u32,
+    pub output_transform: OutputTransform,
+    pub alpha_output: bool,
+    pub transparent_background: bool,
+    pub integrator: String,
+    pub environment_sampled: bool,
     pub bvhroot: BVHNode,
 }
 
@@ -40,14 +60,72 @@ pub struct Canvas {
     pub depth: f64,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Material {
     pub amb: Color,
     pub dif: Color,
     pub spec: Color,
+    // Whether spec also reflects the scene rather than only lights
+    pub mirror: bool,
     pub ns: f64,
     pub trs: Color,
     pub ior: f64,
+    pub fresnel: bool,
+    pub absorption: Color,
+    pub emission: Color,
+    pub emission_sampled: bool,
+    pub diffuse_model: DiffuseModel,
+    pub specular_model: SpecularModel,
+    pub metallic_roughness: Option<MetallicRoughness>,
+    pub textures: MaterialTextures,
+    // Height in scene units of white in the bump map
+    pub bump_scale: f64,
+    pub opacity: f64,
+    pub alpha_cutoff: f64,
+    pub sides: Sides,
+    pub back: Option<Box<Material>>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sides {
+    Both,
+    // Back faces are culled
+    Front,
+    // Front faces are the outside of a closed mesh
+    Solid,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MaterialTextures {
+    pub amb: Option<Texturable>,
+    pub dif: Option<Texturable>,
+    pub spec: Option<Texturable>,
+    pub trs: Option<Texturable>,
+    pub emission: Option<Texturable>,
+    // Tangent space, with red along dP/du and green along dP/dv
+    pub normal: Option<Texturable>,
+    pub bump: Option<Texturable>,
+    pub opacity: Option<Texturable>,
+}
+
+impl MaterialTextures {
+    pub fn set(&mut self, channel: &str, texture: Option<Texturable>) {
+        match channel {
+            "amb" => self.amb = texture,
+            "dif" => self.dif = texture,
+            "spec" => self.spec = texture,
+            "trs" => self.trs = texture,
+            "emission" => self.emission = texture,
+            "normal" => self.normal = texture,
+            "bump" => self.bump = texture,
+            "opacity" => self.opacity = texture,
+            _ => panic!("Unknown material channel {}", channel),
+        }
+    }
+
+    pub fn is_color(channel: &str) -> bool {
+        matches!(channel, "amb" | "dif" | "spec" | "trs" | "emission")
+    }
 }
 
 impl Scene {
@@ -66,6 +144,7 @@ impl Scene {
             g: 0.,
             b: 0.,
         };
+        let mut ambient_occlusion: Option<AmbientOcclusion> = None;
         let mut camera = Camera {
             pos: Vector {
                 x: 0.,
@@ -92,9 +171,25 @@ impl Scene {
         let mut shapes: Vec<Intersectable> = Vec::new();
         let mut lights: Vec<Lightable> = Vec::new();
         let mut max_depth = 5;
+        let mut samples_per_pixel = 1;
+        let mut output_transform = OutputTransform::new();
+        let mut alpha_output = false;
+        let mut transparent_background = false;
+        let mut integrator = String::from("whitted");
 
+        let mut environment: Option<Environmental> = None;
+        let mut camera_environment: Option<Environmental> = None;
+        let mut backplate: Option<Backplate> = None;
+        let mut environment_map: Option<Arc<EnvironmentMap>> = None;
+        let mut environment_light_samples = 0;
         let mut current_material = Material::new();
+        let mut materials: HashMap<String, Material> = HashMap::new();
+        let mut light_samples = 16;
+        let mut light_visible = true;
+        let mut emissive_light_samples = 0;
+        let mut attenuation = Attenuation::new();
         let mut vertices: Vec<Vector> = Vec::new();
+        let mut texcoords: Vec<(f64, f64)> = Vec::new();
 
         let f = File::open(scene_file).expect("error");
         let file = BufReader::new(&f);
@@ -120,7 +215,7 @@ impl Scene {
                     let s = Sphere {
                         pos: Vector { x, y, z },
                         r,
-                        mat: current_material,
+                        mat: current_material.clone(),
                     };
                     // println!(
                     //     "Made new sphere at {} {} {} with radius {}",
@@ -135,6 +230,57 @@ impl Scene {
                     background = Color::new(r, g, b);
                     // println!("Background is now {:?}", background);
                 }
+                "background_gradient" => {
+                    let horizon = parse_color(&mut l_iter);
+                    let zenith = parse_color(&mut l_iter);
+                    environment = Some(Arc::new(Gradient { horizon, zenith }));
+                }
+                "camera_background" => {
+                    // Seen only by camera rays, with reflections still seeing
+                    // the regular background
+                    camera_environment = Some(Arc::new(parse_color(&mut l_iter)));
+                }
+                "camera_background_gradient" => {
+                    let horizon = parse_color(&mut l_iter);
+                    let zenith = parse_color(&mut l_iter);
+                    camera_environment = Some(Arc::new(Gradient { horizon, zenith }));
+                }
+                "backplate" => {
+                    backplate = Some(Backplate::load(l_iter.next().unwrap()));
+                }
+                "environment_map" => {
+                    let file = l_iter.next().unwrap();
+                    let intensity = l_iter.next().map_or(1., |i| i.parse().unwrap());
+                    let map = Arc::new(EnvironmentMap::load(file, intensity));
+                    environment = Some(Arc::clone(&map) as Environmental);
+                    environment_map = Some(map);
+                }
+                "sky" => {
+                    let elevation: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let azimuth: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let turbidity = l_iter.next().unwrap().parse().unwrap();
+                    let intensity = l_iter.next().map_or(1., |i| i.parse().unwrap());
+                    let sun_intensity = l_iter.next().map_or(1., |i| i.parse().unwrap());
+                    let sky = Sky::new(
+                        elevation * PI / 180.,
+                        azimuth * PI / 180.,
+                        turbidity,
+                        intensity,
+                    );
+                    if sun_intensity > 0. && sky.sun.y > 0. {
+                        let l = DirectionalLight {
+                            dir: sky.sun * -1.,
+                            intensity: sky.sun_color() * sun_intensity,
+                        };
+                        lights.push(Arc::new(l));
+                    }
+                    environment = Some(Arc::new(sky));
+                }
+                "environment_light" => {
+                    // Number of samples to light the scene with from the
+                    // environment map
+                    environment_light_samples = l_iter.next().unwrap().parse().unwrap();
+                }
                 "camera" => {
                     let pos_x: f64 = l_iter.next().unwrap().parse().unwrap();
                     let pos_y: f64 = l_iter.next().unwrap().parse().unwrap();
@@ -186,6 +332,41 @@ impl Scene {
                     max_depth = l_iter.next().unwrap().parse().unwrap();
                     // println!("Max recursion depth is {}", max_depth);
                 }
+                "supersample" => {
+                    samples_per_pixel = l_iter.next().unwrap().parse().unwrap();
+                }
+                "exposure" => {
+                    output_transform.exposure = l_iter.next().unwrap().parse().unwrap();
+                }
+                "tone_map" => {
+                    output_transform.tone_map = match l_iter.next().unwrap() {
+                        "clamp" => ToneMap::Clamp,
+                        "reinhard" => ToneMap::Reinhard,
+                        "aces" => ToneMap::Aces,
+                        name => panic!("Unknown tone mapping operator {}", name),
+                    };
+                }
+                "output_encoding" => {
+                    output_transform.srgb = match l_iter.next().unwrap() {
+                        "linear" => false,
+                        "srgb" => true,
+                        name => panic!("Unknown output encoding {}", name),
+                    };
+                }
+                "alpha_output" => {
+                    let on: u32 = l_iter.next().unwrap().parse().unwrap();
+                    alpha_output = on != 0;
+                }
+                "transparent_background" => {
+                    // Also turns on alpha output, which the transparent
+                    // parts would be meaningless without
+                    let on: u32 = l_iter.next().unwrap().parse().unwrap();
+                    transparent_background = on != 0;
+                    alpha_output |= transparent_background;
+                }
+                "integrator" => {
+                    integrator = l_iter.next().unwrap().to_string();
+                }
                 "material" => {
                     let ar = l_iter.next().unwrap().parse().unwrap();
                     let ag = l_iter.next().unwrap().parse().unwrap();
@@ -217,6 +398,7 @@ impl Scene {
                             g: sg,
                             b: sb,
                         },
+                        mirror: true,
                         ns: ns,
                         trs: Color {
                             r: tr,
@@ -224,9 +406,89 @@ impl Scene {
                             b: tb,
                         },
                         ior: ior,
+                        fresnel: false,
+                        absorption: Color::new(0., 0., 0.),
+                        emission: Color::new(0., 0., 0.),
+                        emission_sampled: false,
+                        diffuse_model: DiffuseModel::Lambert,
+                        specular_model: SpecularModel::Phong,
+                        metallic_roughness: None,
+                        textures: MaterialTextures::default(),
+                        bump_scale: 1.,
+                        opacity: 1.,
+                        alpha_cutoff: 0.5,
+                        sides: Sides::Both,
+                        back: None,
                     };
                     // println!("Current material is {:?}", current_material);
                 }
+                "pbr_material" => {
+                    let base_color = parse_color(&mut l_iter);
+                    let metallic = l_iter.next().unwrap().parse().unwrap();
+                    let roughness = l_iter.next().unwrap().parse().unwrap();
+                    let ior = l_iter.next().map_or(1.5, |ior| ior.parse().unwrap());
+                    current_material =
+                        Material::metallic_roughness(base_color, metallic, roughness, ior);
+                }
+                "diffuse_model" => {
+                    current_material.diffuse_model = parse_diffuse_model(&mut l_iter);
+                }
+                "specular_model" => {
+                    current_material.specular_model = parse_specular_model(&mut l_iter);
+                }
+                "emission" => {
+                    let emission = parse_color(&mut l_iter);
+                    let strength: f64 = l_iter.next().map_or(1., |s| s.parse().unwrap());
+                    current_material.emission = emission * strength;
+                }
+                "emissive_lights" => {
+                    // Shadow samples taken by each emissive triangle defined
+                    // after this, or 0 to only show their glow
+                    emissive_light_samples = l_iter.next().unwrap().parse().unwrap();
+                }
+                "texture" => {
+                    // Texture for one of the current material's colors, or
+                    // none to remove it
+                    let channel = l_iter.next().unwrap();
+                    let texture = parse_texture(&mut l_iter, channel, Path::new(""));
+                    current_material.textures.set(channel, texture);
+                }
+                "bump_scale" => {
+                    current_material.bump_scale = l_iter.next().unwrap().parse().unwrap();
+                }
+                "opacity" => {
+                    current_material.opacity = l_iter.next().unwrap().parse().unwrap();
+                    if let Some(cutoff) = l_iter.next() {
+                        current_material.alpha_cutoff = cutoff.parse().unwrap();
+                    }
+                }
+                "sides" => {
+                    current_material.sides = parse_sides(l_iter.next().unwrap());
+                }
+                "back_material" => {
+                    // Named material for back faces, or none to use the
+                    // current material on both
+                    current_material.back = match l_iter.next().unwrap() {
+                        "none" => None,
+                        name => Some(Box::new(
+                            materials
+                                .get(name)
+                                .unwrap_or_else(|| panic!("Unknown material {}", name))
+                                .clone(),
+                        )),
+                    };
+                }
+                "fresnel" => {
+                    let on: u32 = l_iter.next().unwrap().parse().unwrap();
+                    current_material.fresnel = on != 0;
+                }
+                "absorption" => {
+                    let r: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let g: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let b: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let density: f64 = l_iter.next().map_or(1., |d| d.parse().unwrap());
+                    current_material.absorption = Color::new(r, g, b) * density;
+                }
                 "point_light" => {
                     let r = l_iter.next().unwrap().parse().unwrap();
                     let g = l_iter.next().unwrap().parse().unwrap();
@@ -237,6 +499,7 @@ impl Scene {
                     let l = PointLight {
                         pos: Vector { x, y, z },
                         intensity: Color { r, g, b },
+                        attenuation,
                     };
                     lights.push(Arc::new(l));
                     // println!("Added point light at ({}, {}, {}) with color ({}, {}, {})", x, y, z, r, g, b);
@@ -255,12 +518,205 @@ impl Scene {
                     lights.push(Arc::new(l));
                     // println!("Added directional light pointing at ({}, {}, {}) with color ({}, {}, {})", x, y, z, r, g, b);
                 }
+                "spot_light" => {
+                    let r = l_iter.next().unwrap().parse().unwrap();
+                    let g = l_iter.next().unwrap().parse().unwrap();
+                    let b = l_iter.next().unwrap().parse().unwrap();
+                    let x = l_iter.next().unwrap().parse().unwrap();
+                    let y = l_iter.next().unwrap().parse().unwrap();
+                    let z = l_iter.next().unwrap().parse().unwrap();
+                    let dx = l_iter.next().unwrap().parse().unwrap();
+                    let dy = l_iter.next().unwrap().parse().unwrap();
+                    let dz = l_iter.next().unwrap().parse().unwrap();
+                    let inner: f64 = l_iter.next().unwrap().parse().unwrap();
+                    let outer: f64 = l_iter.next().unwrap().parse().unwrap();
+                    if inner < 0. || outer < inner {
+                        panic!(
+                            "Spot light angles {} and {} are not 0 <= inner <= outer",
+                            inner, outer
+                        );
+                    }
+                    let falloff = l_iter.next().map_or(1., |f| f.parse().unwrap());
+                    let cookie = l_iter
+                        .next()
+                        .map(|f| Arc::new(ImageTexture::load_srgb(f, Wrap::Clamp)));
+                    // Cookies are projected onto a plane in front of the light
+                    if cookie.is_some() && outer >= 90. {
+                        panic!("Spot light with a cookie has outer angle {} >= 90", outer);
+                    }
+                    let l = SpotLight {
+                        pos: Vector { x, y, z },
+                        dir: Vector {
+                            x: dx,
+                            y: dy,
+                            z: dz,
+                        },
+                        inner: inner * PI / 180.,
+                        outer: outer * PI / 180.,
+                        falloff,
+                        intensity: Color { r, g, b },
+                        attenuation,
+                        cookie,
+                    };
+                    lights.push(Arc::new(l));
+                }
+                "rectangle_light" => {
+                    let r = l_iter.next().unwrap().parse().unwrap();
+                    let g = l_iter.next().unwrap().parse().unwrap();
+                    let b = l_iter.next().unwrap().parse().unwrap();
+                    let x = l_iter.next().unwrap().parse().unwrap();
+                    let y = l_iter.next().unwrap().parse().unwrap();
+                    let z = l_iter.next().unwrap().parse().unwrap();
+                    let ux = l_iter.next().unwrap().parse().unwrap();
+                    let uy = l_iter.next().unwrap().parse().unwrap();
+                    let uz = l_iter.next().unwrap().parse().unwrap();
+                    let vx = l_iter.next().unwrap().parse().unwrap();
+                    let vy = l_iter.next().unwrap().parse().unwrap();
+                    let vz = l_iter.next().unwrap().parse().unwrap();
+                    let l = RectangleLight {
+                        pos: Vector { x, y, z },
+                        u: Vector {
+                            x: ux,
+                            y: uy,
+                            z: uz,
+                        },
+                        v: Vector {
+                            x: vx,
+                            y: vy,
+                            z: vz,
+                        },
+                        intensity: Color { r, g, b },
+                        attenuation,
+                        sample_count: light_samples,
+                        visible: light_visible,
+                    };
+                    lights.push(Arc::new(l));
+                }
+                "disk_light" => {
+                    let r = l_iter.next().unwrap().parse().unwrap();
+                    let g = l_iter.next().unwrap().parse().unwrap();
+                    let b = l_iter.next().unwrap().parse().unwrap();
+                    let x = l_iter.next().unwrap().parse().unwrap();
+                    let y = l_iter.next().unwrap().parse().unwrap();
+                    let z = l_iter.next().unwrap().parse().unwrap();
+                    let nx = l_iter.next().unwrap().parse().unwrap();
+                    let ny = l_iter.next().unwrap().parse().unwrap();
+                    let nz = l_iter.next().unwrap().parse().unwrap();
+                    let radius = l_iter.next().unwrap().parse().unwrap();
+                    let l = DiskLight {
+                        pos: Vector { x, y, z },
+                        normal: Vector {
+                            x: nx,
+                            y: ny,
+                            z: nz,
+                        },
+                        r: radius,
+                        intensity: Color { r, g, b },
+                        attenuation,
+                        sample_count: light_samples,
+                        visible: light_visible,
+                    };
+                    lights.push(Arc::new(l));
+                }
+                "sphere_light" => {
+                    let r = l_iter.next().unwrap().parse().unwrap();
+                    let g = l_iter.next().unwrap().parse().unwrap();
+                    let b = l_iter.next().unwrap().parse().unwrap();
+                    let x = l_iter.next().unwrap().parse().unwrap();
+                    let y = l_iter.next().unwrap().parse().unwrap();
+                    let z = l_iter.next().unwrap().parse().unwrap();
+                    let radius = l_iter.next().unwrap().parse().unwrap();
+                    let l = SphereLight {
+                        pos: Vector { x, y, z },
+                        r: radius,
+                        intensity: Color { r, g, b },
+                        attenuation,
+                        sample_count: light_samples,
+                        visible: light_visible,
+                    };
+                    lights.push(Arc::new(l));
+                }
+                "light_samples" => {
+                    // Shadow samples taken by area lights defined after this
+                    let n: u32 = l_iter.next().unwrap().parse().unwrap();
+                    light_samples = n.max(1);
+                }
+                "light_visible" => {
+                    // Whether area lights defined after this show up in renders
+                    let on: u32 = l_iter.next().unwrap().parse().unwrap();
+                    light_visible = on != 0;
+                }
+                "attenuation" => {
+                    // Falloff of positional lights defined after this
+                    let (constant, linear, quadratic) = match l_iter.next().unwrap() {
+                        "none" => (1., 0., 0.),
+                        "linear" => (0., 1., 0.),
+                        "quadratic" => (0., 0., 1.),
+                        c => (
+                            c.parse().unwrap(),
+                            l_iter.next().unwrap().parse().unwrap(),
+                            l_iter.next().unwrap().parse().unwrap(),
+                        ),
+                    };
+                    if constant == 0. && linear == 0. && quadratic == 0. {
+                        panic!("Attenuation coefficients cannot all be 0");
+                    }
+                    attenuation.constant = constant;
+                    attenuation.linear = linear;
+                    attenuation.quadratic = quadratic;
+                }
+                "light_range" => {
+                    // Distance past which lights defined after this are
+                    // skipped, or 0 for no limit
+                    let range: f64 = l_iter.next().unwrap().parse().unwrap();
+                    attenuation.range = if range > 0. { Some(range) } else { None };
+                }
                 "ambient_light" => {
                     let r = l_iter.next().unwrap().parse().unwrap();
                     let g = l_iter.next().unwrap().parse().unwrap();
                     let b = l_iter.next().unwrap().parse().unwrap();
                     ambient_light = Color { r, g, b };
                 }
+                "ambient_occlusion" => {
+                    // Number of hemisphere rays, or 0 to turn it off, and an
+                    // optional distance past which nothing occludes
+                    let samples = l_iter.next().unwrap().parse().unwrap();
+                    let mut ao = AmbientOcclusion::new();
+                    ao.samples = samples;
+                    if let Some(d) = l_iter.next() {
+                        ao.max_distance = d.parse().unwrap();
+                    }
+                    ambient_occlusion = if samples > 0 { Some(ao) } else { None };
+                }
+                "define_material" => {
+                    let name = l_iter.next().unwrap().to_string();
+                    let mat = library::define_material(l_iter, Path::new(""), &materials);
+                    materials.insert(name, mat);
+                }
+                "use_material" => {
+                    let name = l_iter.next().unwrap();
+                    current_material = materials
+                        .get(name)
+                        .unwrap_or_else(|| panic!("Unknown material {}", name))
+                        .clone();
+                }
+                "material_library" => {
+                    library::load(Path::new(l_iter.next().unwrap()), &mut materials);
+                }
+                "obj" => {
+                    // Faces without a material of their own use the current
+                    // one
+                    let path = Path::new(l_iter.next().unwrap());
+                    for t in obj::load(path, &current_material, &mut materials) {
+                        add_triangle(
+                            t,
+                            &mut shapes,
+                            &mut lights,
+                            emissive_light_samples,
+                            attenuation,
+                        );
+                    }
+                }
                 "vertex" => {
                     let x = l_iter.next().unwrap().parse().unwrap();
                     let y = l_iter.next().unwrap().parse().unwrap();
@@ -268,22 +724,51 @@ impl Scene {
                     let v = Vector { x, y, z };
                     vertices.push(v);
                 }
+                "texcoord" => {
+                    let u = l_iter.next().unwrap().parse().unwrap();
+                    let v = l_iter.next().unwrap().parse().unwrap();
+                    texcoords.push((u, v));
+                }
                 "triangle" => {
                     let v1: usize = l_iter.next().unwrap().parse().unwrap();
                     let v2: usize = l_iter.next().unwrap().parse().unwrap();
                     let v3: usize = l_iter.next().unwrap().parse().unwrap();
+                    // Optional indexes of the texture coordinates at each
+                    // vertex
+                    let t: Vec<usize> = l_iter.map(|i| i.parse().unwrap()).collect();
+                    let uvs = if t.len() >= 3 {
+                        Some([texcoords[t[0]], texcoords[t[1]], texcoords[t[2]]])
+                    } else {
+                        None
+                    };
                     let t = Triangle {
                         v1: vertices[v1],
                         v2: vertices[v2],
                         v3: vertices[v3],
-                        mat: current_material,
+                        uvs,
+                        mat: current_material.clone(),
                     };
-                    shapes.push(Arc::new(t));
+                    add_triangle(
+                        t,
+                        &mut shapes,
+                        &mut lights,
+                        emissive_light_samples,
+                        attenuation,
+                    );
                     // println!("Added triangle");
                 }
                 _ => continue,
             }
         }
+        let environment_sampled = environment_map.is_some() && environment_light_samples > 0;
+        if let Some(ref map) = environment_map {
+            if environment_sampled {
+                lights.push(Arc::new(EnvironmentLight {
+                    map: Arc::clone(map),
+                    sample_count: environment_light_samples,
+                }));
+            }
+        }
         let canvas = Canvas {
             left: -(width as f64) / 2.,
             bottom: -(height as f64) / 2.,
@@ -295,17 +780,135 @@ impl Scene {
             height,
             filename,
             background,
+            environment,
+            camera_environment,
+            backplate,
             ambient_light,
+            ambient_occlusion,
             camera,
             canvas,
             shapes,
             lights,
             max_depth,
+            samples_per_pixel,
+            output_transform,
+            alpha_output,
+            transparent_background,
+            integrator,
+            environment_sampled,
             bvhroot,
         }
     }
 }
 
+fn add_triangle(
+    mut t: Triangle,
+    shapes: &mut Vec<Intersectable>,
+    lights: &mut Vec<Lightable>,
+    emissive_light_samples: u32,
+    attenuation: Attenuation,
+) {
+    if emissive_light_samples > 0 && t.mat.emission.is_not_black() {
+        t.mat.emission_sampled = true;
+        lights.push(Arc::new(TriangleLight {
+            v1: t.v1,
+            v2: t.v2,
+            v3: t.v3,
+            emission: t.mat.emission,
+            attenuation,
+            sample_count: emissive_light_samples,
+        }));
+    }
+    shapes.push(Arc::new(t));
+}
+
+pub fn parse_color<'a, I: Iterator<Item = &'a str>>(l_iter: &mut I) -> Color {
+    let r = l_iter.next().unwrap().parse().unwrap();
+    let g = l_iter.next().unwrap().parse().unwrap();
+    let b = l_iter.next().unwrap().parse().unwrap();
+    Color { r, g, b }
+}
+
+pub fn parse_sides(sides: &str) -> Sides {
+    match sides {
+        "both" => Sides::Both,
+        "front" => Sides::Front,
+        "solid" => Sides::Solid,
+        _ => panic!("Unknown sides {}", sides),
+    }
+}
+
+pub fn parse_diffuse_model<'a, I: Iterator<Item = &'a str>>(l_iter: &mut I) -> DiffuseModel {
+    match l_iter.next().unwrap() {
+        "lambert" => DiffuseModel::Lambert,
+        "oren_nayar" => {
+            let sigma: f64 = l_iter.next().unwrap().parse().unwrap();
+            DiffuseModel::OrenNayar {
+                sigma: sigma.to_radians(),
+            }
+        }
+        name => panic!("Unknown diffuse model {}", name),
+    }
+}
+
+pub fn parse_specular_model<'a, I: Iterator<Item = &'a str>>(l_iter: &mut I) -> SpecularModel {
+    match l_iter.next().unwrap() {
+        "phong" => SpecularModel::Phong,
+        "blinn_phong" => SpecularModel::BlinnPhong,
+        "ward" => {
+            let ax = l_iter.next().unwrap().parse().unwrap();
+            let ay = l_iter.next().unwrap().parse().unwrap();
+            SpecularModel::Ward { ax, ay }
+        }
+        name => panic!("Unknown specular model {}", name),
+    }
+}
+
+pub fn parse_texture<'a, I: Iterator<Item = &'a str>>(
+    l_iter: &mut I,
+    channel: &str,
+    dir: &Path,
+) -> Option<Texturable> {
+    let kind = l_iter.next().unwrap();
+    let pattern = match kind {
+        "checker" => Some(Pattern::Checker),
+        "noise" => Some(Pattern::Noise),
+        "fbm" => Some(Pattern::Fbm),
+        "turbulence" => Some(Pattern::Turbulence),
+        "marble" => Some(Pattern::Marble),
+        "wood" => Some(Pattern::Wood),
+        _ => None,
+    };
+    match (kind, pattern) {
+        ("none", _) => None,
+        (_, Some(pattern)) => {
+            let a = parse_color(l_iter);
+            let b = parse_color(l_iter);
+            let scale = l_iter.next().unwrap().parse().unwrap();
+            let space = match l_iter.next().unwrap_or("world") {
+                "world" => Space::World,
+                "object" => Space::Object,
+                space => panic!("Unknown texture space {}", space),
+            };
+            Some(Arc::new(Procedural::new(pattern, a, b, scale, space)))
+        }
+        (file, None) => {
+            let wrap = match l_iter.next().unwrap_or("repeat") {
+                "repeat" => Wrap::Repeat,
+                "clamp" => Wrap::Clamp,
+                wrap => panic!("Unknown texture wrap mode {}", wrap),
+            };
+            let path = dir.join(file);
+            let path = path.to_str().unwrap();
+            if MaterialTextures::is_color(channel) {
+                Some(Arc::new(ImageTexture::load_srgb(path, wrap)))
+            } else {
+                Some(Arc::new(ImageTexture::load(path, wrap)))
+            }
+        }
+    }
+}
+
 impl Material {
     pub fn new() -> Material {
         Material {
@@ -324,6 +927,7 @@ impl Material {
                 g: 1.,
                 b: 1.,
             },
+            mirror: true,
             ns: 16.,
             trs: Color {
                 r: 1.,
@@ -331,6 +935,138 @@ impl Material {
                 b: 1.,
             },
             ior: 1.,
+            fresnel: false,
+            absorption: Color {
+                r: 0.,
+                g: 0.,
+                b: 0.,
+            },
+            emission: Color {
+                r: 0.,
+                g: 0.,
+                b: 0.,
+            },
+            emission_sampled: false,
+            diffuse_model: DiffuseModel::Lambert,
+            specular_model: SpecularModel::Phong,
+            metallic_roughness: None,
+            textures: MaterialTextures::default(),
+            bump_scale: 1.,
+            opacity: 1.,
+            alpha_cutoff: 0.5,
+            sides: Sides::Both,
+            back: None,
+        }
+    }
+
+    pub fn metallic_roughness(
+        base_color: Color,
+        metallic: f64,
+        roughness: f64,
+        ior: f64,
+    ) -> Material {
+        let pbr = MetallicRoughness::new(base_color, metallic, roughness, ior);
+        // Mirror reflections stand in for the part of the specular lobe lit
+        // by the surroundings, fading out as the surface gets rougher, and
+        // the ambient term picks up the rest.
+        let smoothness = (1. - pbr.roughness).powi(2);
+        Material {
+            amb: pbr.diffuse_color() + pbr.f0() * (1. - smoothness),
+            dif: pbr.diffuse_color(),
+            spec: pbr.f0() * smoothness,
+            ns: 1.,
+            trs: Color::new(0., 0., 0.),
+            ior,
+            metallic_roughness: Some(pbr),
+            ..Material::new()
+        }
+    }
+
+    pub fn shading_normal(
+        &self,
+        n: Vector,
+        dpdu: Vector,
+        dpdv: Vector,
+        point: &TexturePoint,
+    ) -> Vector {
+        if let Some(ref normal_map) = self.textures.normal {
+            let t = (dpdu - n * n.dot(&dpdu)).normalized();
+            let mut b = n.cross(&t);
+            if b.dot(&dpdv) < 0. {
+                b *= -1.;
+            }
+            let c = normal_map.color(point);
+            return (t * (2. * c.r - 1.) + b * (2. * c.g - 1.) + n * (2. * c.b - 1.)).normalized();
+        }
+        if let Some(ref bump_map) = self.textures.bump {
+            // Finite differences of the height across a small step in each
+            // texture coordinate
+            let delta = 1e-3;
+            let height = |du: f64, dv: f64| {
+                let offset = dpdu * du + dpdv * dv;
+                let p = TexturePoint {
+                    uv: (point.uv.0 + du, point.uv.1 + dv),
+                    world: point.world + offset,
+                    object: point.object + offset,
+                    ..*point
+                };
+                bump_map.color(&p).luminance() * self.bump_scale
+            };
+            let h = height(0., 0.);
+            let dhdu = (height(delta, 0.) - h) / delta;
+            let dhdv = (height(0., delta) - h) / delta;
+            let bumped = (dpdu + n * dhdu).cross(&(dpdv + n * dhdv)).normalized();
+            return if bumped.dot(&n) < 0. {
+                bumped * -1.
+            } else {
+                bumped
+            };
+        }
+        n
+    }
+
+    pub fn at(mut self, point: &TexturePoint) -> Material {
+        let texel = |texture: &Option<Texturable>| texture.as_ref().map(|t| t.color(point));
+        if let Some(c) = texel(&self.textures.amb) {
+            self.amb = self.amb * c;
+        }
+        if let Some(c) = texel(&self.textures.dif) {
+            self.dif = self.dif * c;
+            if let Some(ref mut pbr) = self.metallic_roughness {
+                pbr.base_color = pbr.base_color * c;
+            }
+        }
+        if let Some(c) = texel(&self.textures.spec) {
+            self.spec = self.spec * c;
+        }
+        if let Some(c) = texel(&self.textures.trs) {
+            self.trs = self.trs * c;
+        }
+        if let Some(c) = texel(&self.textures.emission) {
+            self.emission = self.emission * c;
+        }
+        self
+    }
+
+    pub fn is_opaque<F: FnOnce() -> TexturePoint>(&self, point: F) -> bool {
+        let opacity = match self.textures.opacity {
+            Some(ref texture) => self.opacity * texture.opacity(&point()),
+            None => self.opacity,
+        };
+        opacity >= self.alpha_cutoff
+    }
+
+    pub fn diffuse(&self, n: Vector, v: Vector, l: Vector) -> Color {
+        match self.metallic_roughness {
+            Some(pbr) => pbr.diffuse(n, v, l),
+            None => self.dif * self.diffuse_model.reflectance(n, v, l),
+        }
+    }
+
+    pub fn specular(&self, n: Vector, v: Vector, l: Vector) -> Color {
+        match self.metallic_roughness {
+            Some(pbr) => pbr.specular(n, v, l),
+            None => self.spec * self.specular_model.reflectance(n, v, l, self.ns),
         }
     }
 }
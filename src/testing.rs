@@ -0,0 +1,61 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use color::Color;
+use math::Vector;
+
+pub fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-6, "{} is not {}", a, b);
+}
+
+pub fn assert_color(c: Color, r: f64, g: f64, b: f64) {
+    assert!(
+        (c.r - r).abs() < 1e-6 && (c.g - g).abs() < 1e-6 && (c.b - b).abs() < 1e-6,
+        "{:?} is not ({}, {}, {})",
+        c,
+        r,
+        g,
+        b
+    );
+}
+
+pub fn assert_vector(v: Vector, x: f64, y: f64, z: f64) {
+    assert!(
+        (v.x - x).abs() < 1e-6 && (v.y - y).abs() < 1e-6 && (v.z - z).abs() < 1e-6,
+        "{:?} is not ({}, {}, {})",
+        v,
+        x,
+        y,
+        z
+    );
+}
+
+// File in the temp directory, removed when dropped
+pub struct TempFile {
+    pub path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(name: &str) -> TempFile {
+        let path = env::temp_dir().join(format!("raytracer_{}_{}", process::id(), name));
+        TempFile { path }
+    }
+
+    pub fn with_contents(name: &str, contents: &[u8]) -> TempFile {
+        let file = TempFile::new(name);
+        fs::write(&file.path, contents).unwrap();
+        file
+    }
+
+    pub fn name(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,302 @@
+extern crate image;
+
+use std::ops;
+
+use color::Color;
+use texture::{Texture, TexturePoint};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+}
+
+impl Wrap {
+    fn index(&self, i: i64, size: usize) -> usize {
+        match *self {
+            Wrap::Repeat => i.rem_euclid(size as i64) as usize,
+            Wrap::Clamp => i.clamp(0, size as i64 - 1) as usize,
+        }
+    }
+}
+
+trait Texel: Copy {
+    type Value: Copy + ops::Add<Output = Self::Value> + ops::Mul<f64, Output = Self::Value>;
+
+    fn value(self) -> Self::Value;
+    fn from_value(value: Self::Value) -> Self;
+}
+
+impl Texel for Color {
+    type Value = Color;
+
+    fn value(self) -> Color {
+        self
+    }
+
+    fn from_value(value: Color) -> Color {
+        value
+    }
+}
+
+impl Texel for f32 {
+    type Value = f64;
+
+    fn value(self) -> f64 {
+        self as f64
+    }
+
+    fn from_value(value: f64) -> f32 {
+        value as f32
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Level<T> {
+    width: usize,
+    height: usize,
+    pixels: Vec<T>,
+}
+
+impl<T: Texel> Level<T> {
+    fn downsample(&self) -> Level<T> {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let pixel = |x: usize, y: usize| {
+            self.pixels[y.min(self.height - 1) * self.width + x.min(self.width - 1)].value()
+        };
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (x2, y2) = (x * 2, y * 2);
+                let sum =
+                    pixel(x2, y2) + pixel(x2 + 1, y2) + pixel(x2, y2 + 1) + pixel(x2 + 1, y2 + 1);
+                pixels.push(T::from_value(sum * 0.25));
+            }
+        }
+        Level {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    levels: Vec<Level<Color>>,
+    alpha: Option<Vec<Level<f32>>>,
+    pub wrap: Wrap,
+}
+
+impl ImageTexture {
+    pub fn load(filename: &str, wrap: Wrap) -> ImageTexture {
+        ImageTexture::read(filename, wrap, false)
+    }
+
+    pub fn load_srgb(filename: &str, wrap: Wrap) -> ImageTexture {
+        ImageTexture::read(filename, wrap, true)
+    }
+
+    fn read(filename: &str, wrap: Wrap, srgb: bool) -> ImageTexture {
+        let img = image::open(filename)
+            .unwrap_or_else(|e| panic!("Error loading image {}: {}", filename, e));
+        let has_alpha = img.color().has_alpha();
+        let decode = srgb
+            && !matches!(
+                img.color(),
+                image::ColorType::Rgb32F | image::ColorType::Rgba32F
+            );
+        let img = img.to_rgba32f();
+        let (width, height) = (img.width() as usize, img.height() as usize);
+        let levels = pyramid(Level {
+            width,
+            height,
+            pixels: img
+                .pixels()
+                .map(|p| {
+                    let c = Color::new(p[0] as f64, p[1] as f64, p[2] as f64);
+                    if decode {
+                        Color::new(srgb_decode(c.r), srgb_decode(c.g), srgb_decode(c.b))
+                    } else {
+                        c
+                    }
+                })
+                .collect(),
+        });
+        let alpha = if has_alpha {
+            Some(pyramid(Level {
+                width,
+                height,
+                pixels: img.pixels().map(|p| p[3]).collect(),
+            }))
+        } else {
+            None
+        };
+        ImageTexture {
+            levels,
+            alpha,
+            wrap,
+        }
+    }
+
+    pub fn sample(&self, s: f64, t: f64) -> Color {
+        self.bilinear(&self.levels[0], s, t)
+    }
+
+    pub fn filtered(&self, s: f64, t: f64, width: f64) -> Color {
+        self.trilinear(&self.levels, s, t, width)
+    }
+
+    fn trilinear<T: Texel>(&self, levels: &[Level<T>], s: f64, t: f64, width: f64) -> T::Value {
+        if width.is_nan() || width <= 1. {
+            return self.bilinear(&levels[0], s, t);
+        }
+        let level = width.log2().min((levels.len() - 1) as f64);
+        let lower = level.floor() as usize;
+        let upper = (lower + 1).min(levels.len() - 1);
+        let f = level - lower as f64;
+        self.bilinear(&levels[lower], s, t) * (1. - f) + self.bilinear(&levels[upper], s, t) * f
+    }
+
+    fn footprint(&self, point: &TexturePoint) -> f64 {
+        let (width, height) = (self.levels[0].width as f64, self.levels[0].height as f64);
+        (point.dx.0 * width)
+            .hypot(point.dx.1 * height)
+            .max((point.dy.0 * width).hypot(point.dy.1 * height))
+    }
+
+    fn bilinear<T: Texel>(&self, level: &Level<T>, s: f64, t: f64) -> T::Value {
+        let x = s * level.width as f64 - 0.5;
+        let y = t * level.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let pixel = |x: i64, y: i64| {
+            let x = self.wrap.index(x, level.width);
+            let y = self.wrap.index(y, level.height);
+            level.pixels[y * level.width + x].value()
+        };
+        let top = pixel(x0, y0) * (1. - fx) + pixel(x0 + 1, y0) * fx;
+        let bottom = pixel(x0, y0 + 1) * (1. - fx) + pixel(x0 + 1, y0 + 1) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+}
+
+impl Texture for ImageTexture {
+    // Texture coordinates put v = 0 at the bottom of the image
+    fn color(&self, point: &TexturePoint) -> Color {
+        let (u, v) = point.uv;
+        self.filtered(u, 1. - v, self.footprint(point))
+    }
+
+    fn opacity(&self, point: &TexturePoint) -> f64 {
+        match self.alpha {
+            Some(ref alpha) => {
+                let (u, v) = point.uv;
+                self.trilinear(alpha, u, 1. - v, self.footprint(point))
+            }
+            None => self.color(point).luminance(),
+        }
+    }
+}
+
+fn pyramid<T: Texel>(base: Level<T>) -> Vec<Level<T>> {
+    let mut levels = vec![base];
+    while {
+        let last = &levels[levels.len() - 1];
+        last.width > 1 || last.height > 1
+    } {
+        let next = levels[levels.len() - 1].downsample();
+        levels.push(next);
+    }
+    levels
+}
+
+fn srgb_decode(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::Vector;
+    use testing::{assert_close, assert_color};
+
+    // Black and white checkerboard of single pixels
+    fn checker(width: usize, height: usize) -> ImageTexture {
+        let pixels = (0..width * height)
+            .map(|i| {
+                let level = ((i % width + i / width) % 2) as f64;
+                Color::new(level, level, level)
+            })
+            .collect();
+        ImageTexture {
+            levels: pyramid(Level {
+                width,
+                height,
+                pixels,
+            }),
+            alpha: None,
+            wrap: Wrap::Repeat,
+        }
+    }
+
+    fn footprint(texture: &ImageTexture, dx: (f64, f64), dy: (f64, f64)) -> f64 {
+        texture.footprint(&TexturePoint {
+            uv: (0.5, 0.5),
+            world: Vector::new(),
+            object: Vector::new(),
+            dx,
+            dy,
+        })
+    }
+
+    #[test]
+    fn pyramid_halves_down_to_one_pixel() {
+        let sizes: Vec<(usize, usize)> = checker(8, 2)
+            .levels
+            .iter()
+            .map(|l| (l.width, l.height))
+            .collect();
+        assert_eq!(sizes, vec![(8, 2), (4, 1), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn levels_average_the_level_above() {
+        let texture = checker(4, 4);
+        for level in &texture.levels[1..] {
+            for c in &level.pixels {
+                assert_color(*c, 0.5, 0.5, 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn picks_the_level_matching_the_footprint() {
+        let texture = checker(4, 4);
+        // Center of the white second pixel
+        let (s, t) = (0.375, 0.125);
+        assert_color(texture.filtered(s, t, 0.5), 1., 1., 1.);
+        assert_color(texture.filtered(s, t, 1.), 1., 1., 1.);
+        assert_color(texture.filtered(s, t, 2.), 0.5, 0.5, 0.5);
+        // Halfway between the full size image and the next level
+        assert_color(texture.filtered(s, t, 2f64.sqrt()), 0.75, 0.75, 0.75);
+    }
+
+    #[test]
+    fn footprint_is_the_longer_pixel_step_in_texels() {
+        let texture = checker(8, 4);
+        assert_close(footprint(&texture, (0., 0.), (0., 0.)), 0.);
+        assert_close(footprint(&texture, (0.25, 0.), (0., 0.5)), 2.);
+        assert_close(
+            footprint(&texture, (0.3, 0.4), (0.1, 0.)),
+            2.4f64.hypot(1.6),
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+use std::sync::Arc;
+
+use color::Color;
+use math::Vector;
+
+pub use self::image::{ImageTexture, Wrap};
+pub use self::noise::Perlin;
+pub use self::procedural::{Pattern, Procedural, Space};
+
+mod image;
+mod noise;
+mod procedural;
+
+#[derive(Copy, Clone, Debug)]
+pub struct TexturePoint {
+    pub uv: (f64, f64),
+    pub world: Vector,
+    pub object: Vector,
+    pub dx: (f64, f64),
+    pub dy: (f64, f64),
+}
+
+pub trait Texture: fmt::Debug + Send + Sync {
+    fn color(&self, point: &TexturePoint) -> Color;
+    fn opacity(&self, point: &TexturePoint) -> f64 {
+        self.color(point).luminance()
+    }
+}
+
+pub type Texturable = Arc<dyn Texture>;
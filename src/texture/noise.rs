@@ -0,0 +1,114 @@
+use math::Vector;
+
+// Fixed permutation so renders are repeatable
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    perm: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new() -> Perlin {
+        let mut p: Vec<usize> = (0..256).collect();
+        let mut seed: u32 = 0x2545_f491;
+        for i in (1..256).rev() {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let j = (seed >> 8) as usize % (i + 1);
+            p.swap(i, j);
+        }
+        Perlin {
+            perm: p.iter().chain(p.iter()).cloned().collect(),
+        }
+    }
+
+    pub fn noise(&self, p: Vector) -> f64 {
+        let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (x, y, z) = (p.x - xf, p.y - yf, p.z - zf);
+        let xi = (xf as i64 & 255) as usize;
+        let yi = (yf as i64 & 255) as usize;
+        let zi = (zf as i64 & 255) as usize;
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.perm;
+        let a = perm[xi] + yi;
+        let aa = perm[a] + zi;
+        let ab = perm[a + 1] + zi;
+        let b = perm[xi + 1] + yi;
+        let ba = perm[b] + zi;
+        let bb = perm[b + 1] + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(perm[aa], x, y, z), grad(perm[ba], x - 1., y, z)),
+                lerp(
+                    u,
+                    grad(perm[ab], x, y - 1., z),
+                    grad(perm[bb], x - 1., y - 1., z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(perm[aa + 1], x, y, z - 1.),
+                    grad(perm[ba + 1], x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    grad(perm[ab + 1], x, y - 1., z - 1.),
+                    grad(perm[bb + 1], x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+
+    pub fn fbm(&self, p: Vector, octaves: u32) -> f64 {
+        let mut sum = 0.;
+        let mut amplitude = 1.;
+        let mut total = 0.;
+        let mut p = p;
+        for _ in 0..octaves {
+            sum += self.noise(p) * amplitude;
+            total += amplitude;
+            amplitude /= 2.;
+            p *= 2.;
+        }
+        sum / total
+    }
+
+    pub fn turbulence(&self, p: Vector, octaves: u32) -> f64 {
+        let mut sum = 0.;
+        let mut amplitude = 1.;
+        let mut total = 0.;
+        let mut p = p;
+        for _ in 0..octaves {
+            sum += self.noise(p).abs() * amplitude;
+            total += amplitude;
+            amplitude /= 2.;
+            p *= 2.;
+        }
+        (sum / total).min(1.)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
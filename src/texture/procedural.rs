@@ -0,0 +1,75 @@
+use std::f64::consts::PI;
+
+use color::Color;
+use texture::{Perlin, Texture, TexturePoint};
+
+const OCTAVES: u32 = 6;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Space {
+    World,
+    Object,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Pattern {
+    Checker,
+    Noise,
+    Fbm,
+    Turbulence,
+    Marble,
+    Wood,
+}
+
+#[derive(Clone, Debug)]
+pub struct Procedural {
+    pub pattern: Pattern,
+    pub a: Color,
+    pub b: Color,
+    pub scale: f64,
+    pub space: Space,
+    perlin: Perlin,
+}
+
+impl Procedural {
+    pub fn new(pattern: Pattern, a: Color, b: Color, scale: f64, space: Space) -> Procedural {
+        Procedural {
+            pattern,
+            a,
+            b,
+            scale,
+            space,
+            perlin: Perlin::new(),
+        }
+    }
+}
+
+impl Texture for Procedural {
+    fn color(&self, point: &TexturePoint) -> Color {
+        let p = match self.space {
+            Space::World => point.world,
+            Space::Object => point.object,
+        } / self.scale;
+
+        let t = match self.pattern {
+            Pattern::Checker => {
+                // Nudged so surfaces lying on a cell boundary don't flicker
+                // between cells
+                let cell = (p.x + 1e-6).floor() + (p.y + 1e-6).floor() + (p.z + 1e-6).floor();
+                (cell as i64).rem_euclid(2) as f64
+            }
+            Pattern::Noise => 0.5 + 0.5 * self.perlin.noise(p),
+            Pattern::Fbm => 0.5 + 0.5 * self.perlin.fbm(p, OCTAVES),
+            Pattern::Turbulence => self.perlin.turbulence(p, OCTAVES),
+            Pattern::Marble => {
+                let turbulence = self.perlin.turbulence(p, OCTAVES);
+                0.5 + 0.5 * ((p.x + 5. * turbulence) * PI).sin()
+            }
+            Pattern::Wood => {
+                let r = (p.x.powi(2) + p.z.powi(2)).sqrt() + 0.4 * self.perlin.fbm(p, 3);
+                r - r.floor()
+            }
+        };
+        self.a * (1. - t) + self.b * t
+    }
+}
@@ -0,0 +1,140 @@
+use color::Color;
+
+#[derive(Copy, Clone, Debug)]
+pub enum ToneMap {
+    Clamp,
+    // On luminance, scaling saturated colors down as a whole
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMap {
+    fn apply(&self, c: Color) -> Color {
+        match *self {
+            ToneMap::Clamp => c,
+            ToneMap::Reinhard => {
+                let c = c / (1. + c.luminance().max(0.));
+                let max = c.r.max(c.g).max(c.b);
+                if max > 1. {
+                    c / max
+                } else {
+                    c
+                }
+            }
+            ToneMap::Aces => {
+                let curve = |x: f64| {
+                    let x = x.max(0.);
+                    x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)
+                };
+                Color::new(curve(c.r), curve(c.g), curve(c.b))
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct OutputTransform {
+    // In stops, applied before tone mapping
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+    pub srgb: bool,
+}
+
+impl OutputTransform {
+    pub fn new() -> OutputTransform {
+        OutputTransform {
+            exposure: 0.,
+            tone_map: ToneMap::Clamp,
+            srgb: false,
+        }
+    }
+
+    pub fn apply(&self, c: Color) -> Color {
+        let c = self.tone_map.apply(c * 2f64.powf(self.exposure));
+        if self.srgb {
+            Color::new(srgb_encode(c.r), srgb_encode(c.g), srgb_encode(c.b))
+        } else {
+            c
+        }
+    }
+}
+
+fn srgb_encode(x: f64) -> f64 {
+    let x = x.clamp(0., 1.);
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1. / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::assert_close;
+
+    #[test]
+    fn clamp_passes_colors_through_unchanged() {
+        let c = ToneMap::Clamp.apply(Color::new(0.25, 2., -1.));
+        assert_eq!((c.r, c.g, c.b), (0.25, 2., -1.));
+        let out = OutputTransform::new().apply(Color::new(0.25, 2., -1.));
+        assert_eq!((out.r, out.g, out.b), (0.25, 2., -1.));
+    }
+
+    #[test]
+    fn reinhard_compresses_luminance() {
+        let c = ToneMap::Reinhard.apply(Color::new(1., 1., 1.));
+        assert_close(c.r, 0.5);
+        assert_close(c.g, 0.5);
+        assert_close(c.b, 0.5);
+    }
+
+    #[test]
+    fn reinhard_keeps_saturated_channels_within_range() {
+        let c = ToneMap::Reinhard.apply(Color::new(10., 0., 0.));
+        assert_close(c.r, 1.);
+        assert_eq!((c.g, c.b), (0., 0.));
+
+        // Hues are kept while scaling down
+        let c = ToneMap::Reinhard.apply(Color::new(20., 10., 0.));
+        assert_close(c.r, 1.);
+        assert_close(c.g, 0.5);
+    }
+
+    #[test]
+    fn aces_rises_from_black_toward_white() {
+        let curve = |x: f64| ToneMap::Aces.apply(Color::new(x, x, x)).r;
+        assert_close(curve(0.), 0.);
+        let mut last = 0.;
+        for i in 1..100 {
+            let y = curve(i as f64 / 10.);
+            assert!(y > last);
+            last = y;
+        }
+        assert!(last < 1.05 && curve(1000.) > 1.);
+    }
+
+    #[test]
+    fn exposure_scales_by_stops() {
+        let transform = OutputTransform {
+            exposure: 2.,
+            ..OutputTransform::new()
+        };
+        assert_close(transform.apply(Color::new(0.1, 0.1, 0.1)).r, 0.4);
+    }
+
+    #[test]
+    fn srgb_encoding() {
+        assert_close(srgb_encode(0.), 0.);
+        assert_close(srgb_encode(1.), 1.);
+        assert_close(srgb_encode(0.002), 12.92 * 0.002);
+        assert_close(srgb_encode(0.5), 0.735357);
+        assert_close(srgb_encode(2.), 1.);
+        let transform = OutputTransform {
+            srgb: true,
+            ..OutputTransform::new()
+        };
+        assert_close(transform.apply(Color::new(0.5, 0., 1.)).r, 0.735357);
+    }
+}